@@ -0,0 +1,246 @@
+use crate::components::{Component, ComponentRef, Stamp};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::singularity;
+use crate::solution::SolutionIndex;
+use crate::sweep::Sweep;
+use nalgebra::base::{DMatrix, DVector};
+use nalgebra::Complex;
+use std::f64::consts::PI;
+
+/// Complex node voltages and branch currents at every frequency of an AC analysis
+#[derive(Debug, Clone)]
+pub struct AcResult {
+    index: SolutionIndex,
+    frequencies: Vec<f64>,
+    solutions: Vec<DVector<Complex<f64>>>,
+}
+
+#[allow(dead_code)]
+impl AcResult {
+    pub fn frequencies(&self) -> &[f64] {
+        &self.frequencies
+    }
+
+    pub fn len(&self) -> usize {
+        self.frequencies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frequencies.is_empty()
+    }
+
+    /// The full complex MNA solution vector at frequency point `point`
+    pub fn solution(&self, point: usize) -> Option<&DVector<Complex<f64>>> {
+        self.solutions.get(point)
+    }
+
+    /// The phasor voltage of the node called `name` at every frequency
+    pub fn node_voltage(&self, name: &str) -> Result<Vec<Complex<f64>>, SimError> {
+        let row = self.index.node_row(name)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+
+    /// The phasor branch current of a voltage source or inductor at every frequency
+    pub fn branch_current(
+        &self,
+        component: impl Into<ComponentRef>,
+    ) -> Result<Vec<Complex<f64>>, SimError> {
+        let row = self.index.branch_row(component)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Small-signal frequency response. Every independent source is replaced by its AC phasor,
+    /// so sources without an AC value are shorted or opened, and the circuit is solved as
+    /// (A + jωE) x = z at each frequency of `sweep` in hertz.
+    pub fn ac(&mut self, sweep: &Sweep) -> Result<AcResult, SimError> {
+        let frequencies = sweep.values()?;
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let a_mat = self.a_mat.map(|g| Complex::new(g, 0.0));
+        let e_mat = self.reactive_matrix(&index)?.map(|c| Complex::new(c, 0.0));
+        let z_mat = self.ac_source_vector(&index)?;
+
+        let mut solutions = Vec::with_capacity(frequencies.len());
+        for &frequency in &frequencies {
+            let omega = 2.0 * PI * frequency;
+            let y_mat = &a_mat + &e_mat * Complex::new(0.0, omega);
+            let lu = y_mat.clone().full_piv_lu();
+            let x = match lu.solve(&z_mat) {
+                Some(x) if !singularity::is_singular(&lu, &y_mat) => x,
+                _ => return Err(SimError::SingularAtFrequency(frequency)),
+            };
+            solutions.push(x);
+        }
+
+        Ok(AcResult {
+            index,
+            frequencies,
+            solutions,
+        })
+    }
+
+    /// The matrix E of the energy-storage elements, which enter the small-signal system as
+    /// jωE: capacitances in the G block and negated inductances on the D diagonal
+    pub(crate) fn reactive_matrix(&self, index: &SolutionIndex) -> Result<DMatrix<f64>, SimError> {
+        let dim = self.a_mat.nrows();
+        let mut e_mat = DMatrix::<f64>::zeros(dim, dim);
+        for component in self.components() {
+            match component {
+                Component::Capacitor(cap) => {
+                    for Stamp(r, c, val) in cap.get_reactive_gmat_stamps() {
+                        e_mat[(index.row_of_id(r as u64)?, index.row_of_id(c as u64)?)] += val;
+                    }
+                }
+                Component::Inductor(ind) => {
+                    for Stamp(r, c, val) in ind.get_reactive_dmat_stamps() {
+                        e_mat[(index.aux_row(r as u64), index.aux_row(c as u64))] += val;
+                    }
+                }
+                Component::Resistor(_)
+                | Component::IVoltageSource(_)
+                | Component::ICurrentSource(_)
+                | Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_) => {}
+            }
+        }
+        Ok(e_mat)
+    }
+
+    /// The z vector with every independent source replaced by its AC phasor
+    fn ac_source_vector(&self, index: &SolutionIndex) -> Result<DVector<Complex<f64>>, SimError> {
+        let mut z_mat = DVector::<Complex<f64>>::zeros(self.a_mat.nrows());
+        for component in self.components() {
+            match component {
+                Component::IVoltageSource(vs) => {
+                    z_mat[index.aux_row(vs.source_num)] = vs.ac_phasor();
+                }
+                Component::ICurrentSource(is) => {
+                    // Same orientation as the DC stamps: the current leaves source_node
+                    let current = is.ac_phasor();
+                    if is.source_node != 0 {
+                        z_mat[index.row_of_id(is.source_node)?] -= current;
+                    }
+                    if is.sink_node != 0 {
+                        z_mat[index.row_of_id(is.sink_node)?] += current;
+                    }
+                }
+                Component::Resistor(_)
+                | Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_)
+                | Component::Capacitor(_)
+                | Component::Inductor(_) => {}
+            }
+        }
+        Ok(z_mat)
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+    use nalgebra::ComplexField;
+
+    #[test]
+    fn rc_lowpass() {
+        // R=1k, C=1u: corner at 1/(2*pi*RC) = 159.15 Hz
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-6);
+        let v1 = net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Capacitor(c1));
+
+        let corner = 1.0 / (2.0 * PI * 1e-3);
+        let sweep = Sweep::Decade {
+            start: corner / 100.0,
+            stop: corner * 100.0,
+            points_per_decade: 10,
+        };
+        let result = net.ac(&sweep).expect("ac should run");
+        assert_eq!(result.len(), 41);
+
+        let v2 = result.node_voltage("2").unwrap();
+        for (f, v) in result.frequencies().iter().zip(&v2) {
+            let expected = Complex::new(1.0, 0.0) / Complex::new(1.0, f / corner);
+            assert_float_absolute_eq!((v - expected).modulus(), 0.0, 1e-12);
+        }
+        // At the corner: -3dB and -45 degrees
+        assert_float_relative_eq!(v2[20].modulus(), 0.5f64.sqrt(), 1e-9);
+        assert_float_relative_eq!(v2[20].argument().to_degrees(), -45.0, 1e-9);
+
+        // The source current is the capacitor current, flowing out of the + terminal
+        let i1 = result.branch_current(v1).unwrap();
+        assert_float_absolute_eq!(
+            (i1[20] + Complex::new(0.0, 1e-6) * 2.0 * PI * corner * v2[20]).modulus(),
+            0.0,
+            1e-12
+        );
+    }
+
+    #[test]
+    fn current_source_into_rlc() {
+        // A 1A current source drives a parallel RLC tank; at resonance the L and C currents
+        // cancel and the full current flows through R
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 0.0).with_ac(1.0, 30.0);
+        let r1 = resistor::Resistor::new(1, 0, 50.0);
+        let l1 = inductor::Inductor::new(1, 0, 1e-3);
+        let c1 = capacitor::Capacitor::new(1, 0, 1e-6);
+        net.add_component(Component::ICurrentSource(i1));
+        net.add_component(Component::Resistor(r1));
+        let l1 = net.add_component(Component::Inductor(l1));
+        net.add_component(Component::Capacitor(c1));
+
+        let resonance = 1.0 / (2.0 * PI * (1e-3f64 * 1e-6).sqrt());
+        let sweep = Sweep::List(vec![resonance / 10.0, resonance]);
+        let result = net.ac(&sweep).expect("ac should run");
+        let v1 = result.node_voltage("1").unwrap();
+        assert_float_relative_eq!(v1[1].modulus(), 50.0, 1e-9);
+        assert_float_relative_eq!(v1[1].argument().to_degrees(), 30.0, 1e-9);
+
+        let omega = 2.0 * PI * resonance / 10.0;
+        let admittance = Complex::new(1.0 / 50.0, omega * 1e-6 - 1.0 / (omega * 1e-3));
+        let expected =
+            Complex::new(30f64.to_radians().cos(), 30f64.to_radians().sin()) / admittance;
+        assert_float_absolute_eq!((v1[0] - expected).modulus(), 0.0, 1e-12);
+        let i_l = result.branch_current(l1).unwrap();
+        assert_float_absolute_eq!(
+            (i_l[0] - expected / Complex::new(0.0, omega * 1e-3)).modulus(),
+            0.0,
+            1e-12
+        );
+    }
+
+    #[test]
+    fn undamped_resonance_is_singular() {
+        // A series LC across an ideal source shorts it at resonance
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0);
+        let l1 = inductor::Inductor::new(1, 2, 1.0);
+        let c1 = capacitor::Capacitor::new(2, 0, 1.0);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Inductor(l1));
+        net.add_component(Component::Capacitor(c1));
+
+        let resonance = 1.0 / (2.0 * PI);
+        assert_eq!(
+            net.ac(&Sweep::List(vec![resonance / 2.0, resonance]))
+                .unwrap_err(),
+            SimError::SingularAtFrequency(resonance)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::components::Stamp;
+use crate::integration::{Companion, IntegrationMethod};
+use crate::tolerance::Tolerance;
+use crate::DCComponent;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Capacitor {
+    pub a_node: u64,
+    pub b_node: u64,
+    pub capacitance: f64,
+    /// Voltage from a_node to b_node at t=0 when the operating point is skipped
+    pub initial_voltage: Option<f64>,
+    pub tolerance: Option<Tolerance>,
+}
+
+#[allow(dead_code)]
+impl Capacitor {
+    pub fn new(a_node: u64, b_node: u64, capacitance: f64) -> Self {
+        Self {
+            a_node,
+            b_node,
+            capacitance,
+            initial_voltage: None,
+            tolerance: None,
+        }
+    }
+
+    pub fn with_initial_voltage(mut self, initial_voltage: f64) -> Self {
+        self.initial_voltage = Some(initial_voltage);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn is_linear(&self) -> bool {
+        true
+    }
+
+    /// Companion model for the step of length `h` that follows a timepoint where the capacitor
+    /// had voltage `v_prev` and current `i_prev` (both from a_node to b_node)
+    pub fn companion(
+        &self,
+        method: IntegrationMethod,
+        h: f64,
+        v_prev: f64,
+        i_prev: f64,
+    ) -> Companion {
+        Companion::new(method, self.capacitance, h, v_prev, i_prev)
+    }
+
+    /// G matrix stamps of the companion conductance, laid out like a resistor's
+    pub fn get_companion_gmat_stamps(&self, companion: &Companion) -> Vec<Stamp> {
+        self.conductance_stamps(companion.conductance)
+    }
+
+    /// Stamps of the capacitance into the G block of the reactive matrix E, so that the
+    /// small-signal admittance matrix is A + jωE
+    pub fn get_reactive_gmat_stamps(&self) -> Vec<Stamp> {
+        self.conductance_stamps(self.capacitance)
+    }
+
+    fn conductance_stamps(&self, g: f64) -> Vec<Stamp> {
+        let mut ret_vec: Vec<Stamp> = vec![];
+        if self.a_node != 0 {
+            ret_vec.push(Stamp(self.a_node as _, self.a_node as _, g));
+        }
+        if self.b_node != 0 {
+            ret_vec.push(Stamp(self.b_node as _, self.b_node as _, g));
+        }
+        if self.a_node != 0 && self.b_node != 0 {
+            ret_vec.push(Stamp(self.a_node as _, self.b_node as _, -g));
+            ret_vec.push(Stamp(self.b_node as _, self.a_node as _, -g));
+        }
+        ret_vec
+    }
+
+    /// z matrix stamps of the companion history current, which flows into a_node
+    pub fn get_companion_zmat_stamps(&self, companion: &Companion) -> Vec<Stamp> {
+        let mut ret_vec: Vec<Stamp> = vec![];
+        if self.a_node != 0 {
+            ret_vec.push(Stamp(self.a_node as _, 1, companion.current));
+        }
+        if self.b_node != 0 {
+            ret_vec.push(Stamp(self.b_node as _, 1, -companion.current));
+        }
+        ret_vec
+    }
+}
+
+impl DCComponent for Capacitor {
+    // A capacitor is an open circuit at DC, so it contributes nothing to the MNA system
+
+    fn get_gmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_bmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_cmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_dmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_zmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::netlist::Netlist;
+    use crate::validation::Diagnostic;
+    use assert_float_eq::*;
+
+    #[test]
+    fn creation() {
+        let cap = Capacitor::new(0, 1, 1e-6).with_initial_voltage(2.0);
+        assert_eq!(cap.initial_voltage, Some(2.0));
+    }
+
+    #[test]
+    fn companion_stamps() {
+        let cap = Capacitor::new(1, 2, 1e-6);
+        let companion = cap.companion(IntegrationMethod::BackwardEuler, 1e-3, 2.0, 0.0);
+        assert_float_relative_eq!(companion.conductance, 1e-3);
+        assert_float_relative_eq!(companion.current, 2e-3);
+
+        let g_stamps = cap.get_companion_gmat_stamps(&companion);
+        assert_eq!(g_stamps.len(), 4);
+        let z_stamps = cap.get_companion_zmat_stamps(&companion);
+        assert_float_relative_eq!(z_stamps[0].2, 2e-3);
+        assert_float_relative_eq!(z_stamps[1].2, -2e-3);
+
+        let grounded = Capacitor::new(0, 2, 1e-6);
+        assert_eq!(grounded.get_companion_gmat_stamps(&companion).len(), 1);
+        assert_eq!(grounded.get_companion_zmat_stamps(&companion).len(), 1);
+    }
+
+    #[test]
+    fn open_circuit_at_dc() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let r1 = resistor::Resistor::new(1, 2, 1.0);
+        let r2 = resistor::Resistor::new(2, 0, 1.0);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-6);
+        let c2 = capacitor::Capacitor::new(1, 2, 1e-6);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::Capacitor(c1));
+        net.add_component(Component::Capacitor(c2));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        assert!(net.a_mat.nrows() == 3);
+
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 1.0f64);
+    }
+
+    #[test]
+    fn no_dc_path_through_capacitor() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let c1 = capacitor::Capacitor::new(1, 2, 1e-6);
+        let r1 = resistor::Resistor::new(2, 3, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Capacitor(c1));
+        net.add_component(Component::Resistor(r1));
+
+        assert_eq!(
+            net.validate(),
+            vec![Diagnostic::NoDcPathToGround {
+                nodes: vec!["2".to_string(), "3".to_string()]
+            }]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use crate::DCComponent;
 
-use super::Stamp;
+use super::{ComponentRef, Stamp};
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CCCurrentSource {
+    pub control: ComponentRef,
+    // Resolved from `control` by Netlist::initialize_dc_mna
     pub dep_source_num: u64,
     pub source_node: u64,
     pub sink_node: u64,
@@ -13,10 +15,16 @@ pub struct CCCurrentSource {
 
 #[allow(dead_code)]
 impl CCCurrentSource {
-    pub fn new(dep_source_num: u64, source_node: u64, sink_node: u64, gain: f64) -> Self {
+    pub fn new(
+        control: impl Into<ComponentRef>,
+        source_node: u64,
+        sink_node: u64,
+        gain: f64,
+    ) -> Self {
         // TODO: check that sensing/output nodes don't overlap illegally
         Self {
-            dep_source_num,
+            control: control.into(),
+            dep_source_num: 0,
             source_node,
             sink_node,
             gain,
@@ -26,6 +34,14 @@ impl CCCurrentSource {
     pub fn is_linear(&self) -> bool {
         true
     }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+    }
 }
 
 impl DCComponent for CCCurrentSource {
@@ -76,28 +92,29 @@ mod tests {
 
     #[test]
     fn creation() {
-        let _ = CCCurrentSource::new(1, 0, 1, 12.0f64);
+        let _ = CCCurrentSource::new(ComponentHandle(0), 0, 1, 12.0f64);
+        let _ = CCCurrentSource::new("V1", 0, 1, 12.0f64);
     }
 
     #[test]
     fn basic_function_grounded() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 2.0);
-        let v2 = independent_voltage_source::IVoltageSource::new(2, 0, 2, 0.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let v2 = independent_voltage_source::IVoltageSource::new(0, 2, 0.0);
         let r1 = resistor::Resistor::new(1, 2, 2.0);
         let r2 = resistor::Resistor::new(3, 0, 0.5);
-        let cccs = cc_current_source::CCCurrentSource::new(2, 3, 0, 2.0);
 
         net.add_component(Component::IVoltageSource(v1));
-        net.add_component(Component::IVoltageSource(v2));
+        let v2 = net.add_component(Component::IVoltageSource(v2));
+        let cccs = cc_current_source::CCCurrentSource::new(v2, 3, 0, 2.0);
         net.add_component(Component::Resistor(r1));
         net.add_component(Component::Resistor(r2));
         net.add_component(Component::CCCurrentSource(cccs));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
@@ -107,4 +124,29 @@ mod tests {
         assert_float_relative_eq!(node_voltages.view((2 - 1, 0), (1, 1))[(0, 0)], 0.0f64);
         assert_float_relative_eq!(node_voltages.view((3 - 1, 0), (1, 1))[(0, 0)], 1.0f64);
     }
+
+    #[test]
+    fn control_by_name() {
+        // Same circuit as basic_function_grounded, with the CCCS added before its control
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let v2 = independent_voltage_source::IVoltageSource::new(0, 2, 0.0);
+        let r1 = resistor::Resistor::new(1, 2, 2.0);
+        let r2 = resistor::Resistor::new(3, 0, 0.5);
+        let cccs = cc_current_source::CCCurrentSource::new("Vsense", 3, 0, 2.0);
+
+        net.add_component(Component::CCCurrentSource(cccs));
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_named_component("Vsense", Component::IVoltageSource(v2))
+            .expect("Vsense is a new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_node_voltage("3").unwrap(), 1.0f64);
+    }
 }
@@ -1,13 +1,14 @@
-use super::Stamp;
+use super::{ComponentRef, Stamp};
 use crate::DCComponent;
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CCVoltageSource {
+    // Assigned by Netlist::add_component
     pub source_num: u64,
+    pub control: ComponentRef,
+    // Resolved from `control` by Netlist::initialize_dc_mna
     pub dep_source_num: u64,
-    pub source_sensing_node: u64,
-    pub sink_sensing_node: u64,
     pub positive_node: u64, // pub source_node: u64,
     pub negative_node: u64, //pub sink_node: u64,
     gain: f64,
@@ -16,20 +17,16 @@ pub struct CCVoltageSource {
 #[allow(dead_code)]
 impl CCVoltageSource {
     pub fn new(
-        source_num: u64,
-        dep_source_num: u64,
-        source_sensing_node: u64,
-        sink_sensing_node: u64,
+        control: impl Into<ComponentRef>,
         positive_node: u64, //source_node: u64,
         negative_node: u64, //sink_node: u64,
         gain: f64,
     ) -> Self {
         // TODO: check that sensing/output nodes don't overlap illegally
         Self {
-            source_num,
-            dep_source_num,
-            source_sensing_node,
-            sink_sensing_node,
+            source_num: 0,
+            control: control.into(),
+            dep_source_num: 0,
             positive_node,
             negative_node,
             gain,
@@ -39,6 +36,14 @@ impl CCVoltageSource {
     pub fn is_linear(&self) -> bool {
         true
     }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+    }
 }
 
 impl DCComponent for CCVoltageSource {
@@ -107,28 +112,29 @@ mod tests {
 
     #[test]
     fn creation() {
-        let _ = CCVoltageSource::new(0, 1, 0, 1, 0, 1, 12.0f64);
+        let _ = CCVoltageSource::new(ComponentHandle(1), 0, 1, 12.0f64);
+        let _ = CCVoltageSource::new("V1", 0, 1, 12.0f64);
     }
 
     #[test]
     fn basic_function_grounded() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 2.0);
-        let v2 = independent_voltage_source::IVoltageSource::new(2, 2, 1, 0.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let v2 = independent_voltage_source::IVoltageSource::new(2, 1, 0.0);
         let r1 = resistor::Resistor::new(2, 3, 1.0);
         let r2 = resistor::Resistor::new(0, 3, 1.0);
-        let ccvs = cc_voltage_source::CCVoltageSource::new(3, 2, 1, 2, 3, 0, 1.0);
 
         net.add_component(Component::IVoltageSource(v1));
-        net.add_component(Component::IVoltageSource(v2));
+        let v2 = net.add_component(Component::IVoltageSource(v2));
+        let ccvs = cc_voltage_source::CCVoltageSource::new(v2, 3, 0, 1.0);
         net.add_component(Component::Resistor(r1));
         net.add_component(Component::Resistor(r2));
         net.add_component(Component::CCVoltageSource(ccvs));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
@@ -142,7 +148,7 @@ mod tests {
     fn basic_function_ungrounded() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 2.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
         let r1 = resistor::Resistor::new(1, 2, 1.0);
         let r2 = resistor::Resistor::new(0, 2, 1.0);
         let vccs = vc_current_source::VCCurrentSource::new(2, 0, 4, 3, 1.0);
@@ -156,9 +162,9 @@ mod tests {
         net.add_component(Component::Resistor(r4));
         net.add_component(Component::VCCurrentSource(vccs));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
@@ -1,27 +1,87 @@
 use super::Stamp;
+use crate::tolerance::Tolerance;
+use crate::waveform::Waveform;
 use crate::DCComponent;
+use nalgebra::Complex;
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ICurrentSource {
     pub source_node: u64,
     pub sink_node: u64,
-    current: f64,
+    current: Waveform,
+    // Small-signal magnitude and phase in degrees
+    ac: (f64, f64),
+    pub tolerance: Option<Tolerance>,
 }
 
 #[allow(dead_code)]
 impl ICurrentSource {
     pub fn new(source_node: u64, sink_node: u64, current: f64) -> Self {
+        Self::from_waveform(source_node, sink_node, Waveform::Dc(current))
+    }
+
+    pub fn from_waveform(source_node: u64, sink_node: u64, current: Waveform) -> Self {
         Self {
             source_node,
             sink_node,
             current,
+            ac: (0.0, 0.0),
+            tolerance: None,
         }
     }
 
+    /// Sets the magnitude and phase (in degrees) of the source in AC analysis
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.ac = (magnitude, phase);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// The small-signal current as a phasor
+    pub fn ac_phasor(&self) -> Complex<f64> {
+        let (magnitude, phase) = self.ac;
+        let phase = phase.to_radians();
+        Complex::new(magnitude * phase.cos(), magnitude * phase.sin())
+    }
+
     pub fn is_linear(&self) -> bool {
         true
     }
+
+    pub fn waveform(&self) -> &Waveform {
+        &self.current
+    }
+
+    pub fn set_waveform(&mut self, current: Waveform) {
+        self.current = current;
+    }
+
+    /// The source current at time `t`
+    pub fn current_at(&self, t: f64) -> f64 {
+        self.current.value(t)
+    }
+
+    /// z matrix stamps with the source evaluated at time `t`
+    pub fn get_zmat_stamps_at(&self, t: f64) -> Vec<Stamp> {
+        let current = self.current_at(t);
+        // The z matrix is 1×(M+N) (N is the number of nodes, and M is the number of independent
+        //   voltage sources) and:
+        //    • the i matrix is 1×N and contains the sum of the currents through the passive elements into
+        //      the corresponding node (either zero, or the sum of independent current sources
+        let mut retvec: Vec<Stamp> = vec![];
+        if self.source_node != 0 {
+            retvec.push(Stamp(self.source_node as _, 1, -current));
+        }
+        if self.sink_node != 0 {
+            retvec.push(Stamp(self.sink_node as _, 1, current));
+        }
+        retvec
+    }
 }
 
 impl DCComponent for ICurrentSource {
@@ -42,18 +102,8 @@ impl DCComponent for ICurrentSource {
     }
 
     fn get_zmat_stamps(&self) -> Vec<Stamp> {
-        // The z matrix is 1×(M+N) (N is the number of nodes, and M is the number of independent
-        //   voltage sources) and:
-        //    • the i matrix is 1×N and contains the sum of the currents through the passive elements into
-        //      the corresponding node (either zero, or the sum of independent current sources
-        let mut retvec: Vec<Stamp> = vec![];
-        if self.source_node != 0 {
-            retvec.push(Stamp(self.source_node as _, 1, -self.current));
-        }
-        if self.sink_node != 0 {
-            retvec.push(Stamp(self.sink_node as _, 1, self.current));
-        }
-        retvec
+        // The operating point sees the value at t=0
+        self.get_zmat_stamps_at(0.0)
     }
 }
 
@@ -66,4 +116,17 @@ mod tests {
     fn creation() {
         let _ = ICurrentSource::new(0, 1, 12.0f64);
     }
+
+    #[test]
+    fn waveform_stamps() {
+        let is = ICurrentSource::from_waveform(1, 2, Waveform::Pwl(vec![(0.0, 1.0), (1.0, 3.0)]));
+        assert_eq!(
+            is.get_zmat_stamps(),
+            vec![Stamp(1, 1, -1.0), Stamp(2, 1, 1.0)]
+        );
+        assert_eq!(
+            is.get_zmat_stamps_at(0.5),
+            vec![Stamp(1, 1, -2.0), Stamp(2, 1, 2.0)]
+        );
+    }
 }
@@ -1,29 +1,83 @@
 use super::Stamp;
+use crate::tolerance::Tolerance;
+use crate::waveform::Waveform;
 use crate::DCComponent;
+use nalgebra::Complex;
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IVoltageSource {
+    // Assigned by Netlist::add_component
     pub source_num: u64,
     pub positive_node: u64,
     pub negative_node: u64,
-    voltage: f64,
+    voltage: Waveform,
+    // Small-signal magnitude and phase in degrees
+    ac: (f64, f64),
+    pub tolerance: Option<Tolerance>,
 }
 
 #[allow(dead_code)]
 impl IVoltageSource {
-    pub fn new(source_num: u64, positive_node: u64, negative_node: u64, voltage: f64) -> Self {
+    pub fn new(positive_node: u64, negative_node: u64, voltage: f64) -> Self {
+        Self::from_waveform(positive_node, negative_node, Waveform::Dc(voltage))
+    }
+
+    pub fn from_waveform(positive_node: u64, negative_node: u64, voltage: Waveform) -> Self {
         Self {
-            source_num,
+            source_num: 0,
             positive_node,
             negative_node,
             voltage,
+            ac: (0.0, 0.0),
+            tolerance: None,
         }
     }
 
+    /// Sets the magnitude and phase (in degrees) of the source in AC analysis
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.ac = (magnitude, phase);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// The small-signal voltage as a phasor
+    pub fn ac_phasor(&self) -> Complex<f64> {
+        let (magnitude, phase) = self.ac;
+        let phase = phase.to_radians();
+        Complex::new(magnitude * phase.cos(), magnitude * phase.sin())
+    }
+
     pub fn is_linear(&self) -> bool {
         true
     }
+
+    pub fn waveform(&self) -> &Waveform {
+        &self.voltage
+    }
+
+    pub fn set_waveform(&mut self, voltage: Waveform) {
+        self.voltage = voltage;
+    }
+
+    /// The source voltage at time `t`
+    pub fn voltage_at(&self, t: f64) -> f64 {
+        self.voltage.value(t)
+    }
+
+    /// z matrix stamps with the source evaluated at time `t`
+    pub fn get_zmat_stamps_at(&self, t: f64) -> Vec<Stamp> {
+        // The z matrix is 1×(M+N) (N is the number of nodes, and M is the number of independent
+        //    voltage sources) and:
+        //    • the i matrix is 1×N and contains the sum of the currents through the passive elements into
+        //    the corresponding node (either zero, or the sum of independent current sources)
+        //    • the e matrix is 1×M and holds the values of the independent voltage sources
+        vec![Stamp(self.source_num as _, 1, self.voltage_at(t))]
+    }
 }
 
 impl DCComponent for IVoltageSource {
@@ -70,12 +124,8 @@ impl DCComponent for IVoltageSource {
     }
 
     fn get_zmat_stamps(&self) -> Vec<Stamp> {
-        // The z matrix is 1×(M+N) (N is the number of nodes, and M is the number of independent
-        //    voltage sources) and:
-        //    • the i matrix is 1×N and contains the sum of the currents through the passive elements into
-        //    the corresponding node (either zero, or the sum of independent current sources)
-        //    • the e matrix is 1×M and holds the values of the independent voltage sources
-        vec![Stamp(self.source_num as _, 1, self.voltage)]
+        // The operating point sees the value at t=0
+        self.get_zmat_stamps_at(0.0)
     }
 }
 
@@ -86,6 +136,15 @@ mod tests {
 
     #[test]
     fn creation() {
-        let _ = IVoltageSource::new(1, 1, 0, 12.0f64);
+        let _ = IVoltageSource::new(1, 0, 12.0f64);
+    }
+
+    #[test]
+    fn waveform_stamps() {
+        let mut vs =
+            IVoltageSource::from_waveform(1, 0, Waveform::Pwl(vec![(0.0, 1.0), (1.0, 3.0)]));
+        vs.source_num = 1;
+        assert_eq!(vs.get_zmat_stamps(), vec![Stamp(1, 1, 1.0)]);
+        assert_eq!(vs.get_zmat_stamps_at(0.5), vec![Stamp(1, 1, 2.0)]);
     }
 }
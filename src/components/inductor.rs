@@ -0,0 +1,194 @@
+use crate::components::Stamp;
+use crate::integration::{Companion, IntegrationMethod};
+use crate::tolerance::Tolerance;
+use crate::DCComponent;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Inductor {
+    // Assigned by Netlist::add_component
+    pub source_num: u64,
+    pub a_node: u64,
+    pub b_node: u64,
+    pub inductance: f64,
+    /// Current from a_node to b_node at t=0 when the operating point is skipped
+    pub initial_current: Option<f64>,
+    pub tolerance: Option<Tolerance>,
+}
+
+#[allow(dead_code)]
+impl Inductor {
+    pub fn new(a_node: u64, b_node: u64, inductance: f64) -> Self {
+        Self {
+            source_num: 0,
+            a_node,
+            b_node,
+            inductance,
+            initial_current: None,
+            tolerance: None,
+        }
+    }
+
+    pub fn with_initial_current(mut self, initial_current: f64) -> Self {
+        self.initial_current = Some(initial_current);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn is_linear(&self) -> bool {
+        true
+    }
+
+    /// Companion model for the step of length `h` that follows a timepoint where the inductor
+    /// carried current `i_prev` with voltage `v_prev` across it. The roles of voltage and current
+    /// are swapped relative to a capacitor: `v = conductance * i - current`.
+    pub fn companion(
+        &self,
+        method: IntegrationMethod,
+        h: f64,
+        i_prev: f64,
+        v_prev: f64,
+    ) -> Companion {
+        Companion::new(method, self.inductance, h, i_prev, v_prev)
+    }
+
+    /// D matrix stamp turning the DC short into V(a) - V(b) - R_eq * i = -V_eq
+    pub fn get_companion_dmat_stamps(&self, companion: &Companion) -> Vec<Stamp> {
+        vec![Stamp(
+            self.source_num as _,
+            self.source_num as _,
+            -companion.conductance,
+        )]
+    }
+
+    /// Stamp of the inductance into the D block of the reactive matrix E, turning the branch
+    /// equation into V(a) - V(b) - jωL * i = 0
+    pub fn get_reactive_dmat_stamps(&self) -> Vec<Stamp> {
+        vec![Stamp(
+            self.source_num as _,
+            self.source_num as _,
+            -self.inductance,
+        )]
+    }
+
+    pub fn get_companion_zmat_stamps(&self, companion: &Companion) -> Vec<Stamp> {
+        vec![Stamp(self.source_num as _, 1, -companion.current)]
+    }
+}
+
+impl DCComponent for Inductor {
+    // At DC an inductor is a short, modelled as a 0V source so its current is an MNA unknown
+
+    fn get_gmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_bmat_stamps(&self) -> Vec<Stamp> {
+        let mut ret_vec: Vec<Stamp> = vec![];
+        if self.a_node != 0 {
+            ret_vec.push(Stamp(self.a_node as _, self.source_num as _, 1.0));
+        }
+        if self.b_node != 0 {
+            ret_vec.push(Stamp(self.b_node as _, self.source_num as _, -1.0));
+        }
+        ret_vec
+    }
+
+    fn get_cmat_stamps(&self) -> Vec<Stamp> {
+        let mut ret_vec: Vec<Stamp> = vec![];
+        if self.a_node != 0 {
+            ret_vec.push(Stamp(self.source_num as _, self.a_node as _, 1.0));
+        }
+        if self.b_node != 0 {
+            ret_vec.push(Stamp(self.source_num as _, self.b_node as _, -1.0));
+        }
+        ret_vec
+    }
+
+    fn get_dmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+
+    fn get_zmat_stamps(&self) -> Vec<Stamp> {
+        vec![]
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::netlist::Netlist;
+    use crate::validation::Diagnostic;
+    use assert_float_eq::*;
+
+    #[test]
+    fn creation() {
+        let ind = Inductor::new(0, 1, 1e-3).with_initial_current(0.5);
+        assert_eq!(ind.initial_current, Some(0.5));
+    }
+
+    #[test]
+    fn companion_stamps() {
+        let mut ind = Inductor::new(1, 2, 1e-3);
+        ind.source_num = 2;
+        let companion = ind.companion(IntegrationMethod::BackwardEuler, 1e-6, 0.5, 0.0);
+        assert_float_relative_eq!(companion.conductance, 1e3);
+        assert_float_relative_eq!(companion.current, 500.0);
+
+        let d_stamps = ind.get_companion_dmat_stamps(&companion);
+        assert_eq!((d_stamps[0].0, d_stamps[0].1), (2, 2));
+        assert_float_relative_eq!(d_stamps[0].2, -1e3);
+        let z_stamps = ind.get_companion_zmat_stamps(&companion);
+        assert_float_relative_eq!(z_stamps[0].2, -500.0);
+    }
+
+    #[test]
+    fn short_circuit_at_dc() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let r1 = resistor::Resistor::new(1, 2, 1.0);
+        let l1 = inductor::Inductor::new(2, 3, 1e-3);
+        let r2 = resistor::Resistor::new(3, 0, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        let l1 = net.add_component(Component::Inductor(l1));
+        net.add_component(Component::Resistor(r2));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        // 3 nodes plus the branch currents of v1 and l1
+        assert!(net.a_mat.nrows() == 5);
+
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 1.0f64);
+        assert_float_relative_eq!(net.get_node_voltage("3").unwrap(), 1.0f64);
+        assert_float_relative_eq!(net.get_branch_current(l1).unwrap(), 1.0f64);
+    }
+
+    #[test]
+    fn inductor_loop() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let l1 = inductor::Inductor::new(1, 0, 1e-3);
+
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("L1", Component::Inductor(l1))
+            .expect("new name");
+
+        assert_eq!(
+            net.validate(),
+            vec![Diagnostic::VoltageSourceLoop {
+                components: vec!["V1".to_string(), "L1".to_string()]
+            }]
+        );
+    }
+}
@@ -1,16 +1,50 @@
+pub mod capacitor;
 pub mod cc_current_source;
 pub mod cc_voltage_source;
 pub mod independent_current_source;
 pub mod independent_voltage_source;
+pub mod inductor;
 pub mod resistor;
 pub mod vc_current_source;
+pub mod vc_voltage_source;
 
 use crate::DCComponent;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Stamp(pub usize, pub usize, pub f64);
 
+/// Identifies a component within the `Netlist` it was added to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentHandle(pub usize);
+
+/// Refers to a component either by the handle `Netlist::add_component` returned for it, or by the
+/// name it was added under with `Netlist::add_named_component`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentRef {
+    Handle(ComponentHandle),
+    Name(String),
+}
+
+impl From<ComponentHandle> for ComponentRef {
+    fn from(handle: ComponentHandle) -> Self {
+        ComponentRef::Handle(handle)
+    }
+}
+
+impl From<&str> for ComponentRef {
+    fn from(name: &str) -> Self {
+        ComponentRef::Name(name.to_string())
+    }
+}
+
+impl From<String> for ComponentRef {
+    fn from(name: String) -> Self {
+        ComponentRef::Name(name)
+    }
+}
+
 #[allow(dead_code)]
+#[derive(Debug, Clone)]
 pub enum Component {
     Resistor(resistor::Resistor),
     IVoltageSource(independent_voltage_source::IVoltageSource),
@@ -18,6 +52,9 @@ pub enum Component {
     VCCurrentSource(vc_current_source::VCCurrentSource),
     CCCurrentSource(cc_current_source::CCCurrentSource),
     CCVoltageSource(cc_voltage_source::CCVoltageSource),
+    VCVoltageSource(vc_voltage_source::VCVoltageSource),
+    Capacitor(capacitor::Capacitor),
+    Inductor(inductor::Inductor),
 }
 
 impl Component {
@@ -29,6 +66,68 @@ impl Component {
             Component::VCCurrentSource(vccs) => vccs.is_linear(),
             Component::CCCurrentSource(cccs) => cccs.is_linear(),
             Component::CCVoltageSource(ccvs) => ccvs.is_linear(),
+            Component::VCVoltageSource(vcvs) => vcvs.is_linear(),
+            Component::Capacitor(cap) => cap.is_linear(),
+            Component::Inductor(ind) => ind.is_linear(),
+        }
+    }
+
+    /// The 1-based index of this component's branch current among the auxiliary MNA variables,
+    /// or None if it does not introduce one
+    pub fn source_num(&self) -> Option<u64> {
+        match self {
+            Component::IVoltageSource(vs) => Some(vs.source_num),
+            Component::CCVoltageSource(ccvs) => Some(ccvs.source_num),
+            Component::VCVoltageSource(vcvs) => Some(vcvs.source_num),
+            Component::Inductor(ind) => Some(ind.source_num),
+            Component::Resistor(_)
+            | Component::ICurrentSource(_)
+            | Component::VCCurrentSource(_)
+            | Component::CCCurrentSource(_)
+            | Component::Capacitor(_) => None,
+        }
+    }
+
+    pub(crate) fn set_source_num(&mut self, source_num: u64) {
+        match self {
+            Component::IVoltageSource(vs) => vs.source_num = source_num,
+            Component::CCVoltageSource(ccvs) => ccvs.source_num = source_num,
+            Component::VCVoltageSource(vcvs) => vcvs.source_num = source_num,
+            Component::Inductor(ind) => ind.source_num = source_num,
+            Component::Resistor(_)
+            | Component::ICurrentSource(_)
+            | Component::VCCurrentSource(_)
+            | Component::CCCurrentSource(_)
+            | Component::Capacitor(_) => {}
+        }
+    }
+
+    /// The source whose branch current controls this component, for current-controlled sources
+    pub fn controlling_source(&self) -> Option<&ComponentRef> {
+        match self {
+            Component::CCCurrentSource(cccs) => Some(&cccs.control),
+            Component::CCVoltageSource(ccvs) => Some(&ccvs.control),
+            Component::Resistor(_)
+            | Component::IVoltageSource(_)
+            | Component::ICurrentSource(_)
+            | Component::VCCurrentSource(_)
+            | Component::VCVoltageSource(_)
+            | Component::Capacitor(_)
+            | Component::Inductor(_) => None,
+        }
+    }
+
+    pub(crate) fn set_dep_source_num(&mut self, dep_source_num: u64) {
+        match self {
+            Component::CCCurrentSource(cccs) => cccs.dep_source_num = dep_source_num,
+            Component::CCVoltageSource(ccvs) => ccvs.dep_source_num = dep_source_num,
+            Component::Resistor(_)
+            | Component::IVoltageSource(_)
+            | Component::ICurrentSource(_)
+            | Component::VCCurrentSource(_)
+            | Component::VCVoltageSource(_)
+            | Component::Capacitor(_)
+            | Component::Inductor(_) => {}
         }
     }
 }
@@ -42,6 +141,9 @@ impl DCComponent for Component {
             Component::VCCurrentSource(vccs) => vccs.get_gmat_stamps(),
             Component::CCCurrentSource(cccs) => cccs.get_gmat_stamps(),
             Component::CCVoltageSource(ccvs) => ccvs.get_gmat_stamps(),
+            Component::VCVoltageSource(vcvs) => vcvs.get_gmat_stamps(),
+            Component::Capacitor(cap) => cap.get_gmat_stamps(),
+            Component::Inductor(ind) => ind.get_gmat_stamps(),
         }
     }
     fn get_bmat_stamps(&self) -> Vec<Stamp> {
@@ -52,6 +154,9 @@ impl DCComponent for Component {
             Component::VCCurrentSource(vccs) => vccs.get_bmat_stamps(),
             Component::CCCurrentSource(cccs) => cccs.get_bmat_stamps(),
             Component::CCVoltageSource(ccvs) => ccvs.get_bmat_stamps(),
+            Component::VCVoltageSource(vcvs) => vcvs.get_bmat_stamps(),
+            Component::Capacitor(cap) => cap.get_bmat_stamps(),
+            Component::Inductor(ind) => ind.get_bmat_stamps(),
         }
     }
     fn get_cmat_stamps(&self) -> Vec<Stamp> {
@@ -62,6 +167,9 @@ impl DCComponent for Component {
             Component::VCCurrentSource(vccs) => vccs.get_cmat_stamps(),
             Component::CCCurrentSource(cccs) => cccs.get_cmat_stamps(),
             Component::CCVoltageSource(ccvs) => ccvs.get_cmat_stamps(),
+            Component::VCVoltageSource(vcvs) => vcvs.get_cmat_stamps(),
+            Component::Capacitor(cap) => cap.get_cmat_stamps(),
+            Component::Inductor(ind) => ind.get_cmat_stamps(),
         }
     }
     fn get_dmat_stamps(&self) -> Vec<Stamp> {
@@ -72,6 +180,9 @@ impl DCComponent for Component {
             Component::VCCurrentSource(vccs) => vccs.get_dmat_stamps(),
             Component::CCCurrentSource(cccs) => cccs.get_dmat_stamps(),
             Component::CCVoltageSource(ccvs) => ccvs.get_dmat_stamps(),
+            Component::VCVoltageSource(vcvs) => vcvs.get_dmat_stamps(),
+            Component::Capacitor(cap) => cap.get_dmat_stamps(),
+            Component::Inductor(ind) => ind.get_dmat_stamps(),
         }
     }
     fn get_zmat_stamps(&self) -> Vec<Stamp> {
@@ -82,6 +193,9 @@ impl DCComponent for Component {
             Component::VCCurrentSource(vccs) => vccs.get_zmat_stamps(),
             Component::CCCurrentSource(cccs) => cccs.get_zmat_stamps(),
             Component::CCVoltageSource(ccvs) => ccvs.get_zmat_stamps(),
+            Component::VCVoltageSource(vcvs) => vcvs.get_zmat_stamps(),
+            Component::Capacitor(cap) => cap.get_zmat_stamps(),
+            Component::Inductor(ind) => ind.get_zmat_stamps(),
         }
     }
 }
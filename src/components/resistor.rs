@@ -1,12 +1,14 @@
 use crate::components::Stamp;
+use crate::tolerance::Tolerance;
 use crate::DCComponent;
 
 #[allow(dead_code)]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Resistor {
     pub a_node: u64,
     pub b_node: u64,
     pub resistance: f64,
+    pub tolerance: Option<Tolerance>,
 }
 
 #[allow(dead_code)]
@@ -16,10 +18,16 @@ impl Resistor {
             a_node,
             b_node,
             resistance,
+            tolerance: None,
             //..Default::default()
         }
     }
 
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
     pub fn is_linear(&self) -> bool {
         true
     }
@@ -3,7 +3,7 @@ use crate::DCComponent;
 use super::Stamp;
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VCCurrentSource {
     pub source_sensing_node: u64,
     pub sink_sensing_node: u64,
@@ -34,6 +34,14 @@ impl VCCurrentSource {
     pub fn is_linear(&self) -> bool {
         true
     }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+    }
 }
 
 impl DCComponent for VCCurrentSource {
@@ -104,7 +112,7 @@ mod tests {
     fn basic_function_grounded() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 2.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
         let r1 = resistor::Resistor::new(1, 2, 1.0);
         let r2 = resistor::Resistor::new(0, 2, 1.0);
         let vccs = vc_current_source::VCCurrentSource::new(2, 0, 3, 0, -1.0);
@@ -116,9 +124,9 @@ mod tests {
         net.add_component(Component::Resistor(r3));
         net.add_component(Component::VCCurrentSource(vccs));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
@@ -132,7 +140,7 @@ mod tests {
     fn basic_function_ungrounded() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 2.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
         let r1 = resistor::Resistor::new(1, 2, 1.0);
         let r2 = resistor::Resistor::new(0, 2, 1.0);
         let vccs = vc_current_source::VCCurrentSource::new(2, 0, 4, 3, 1.0);
@@ -146,9 +154,9 @@ mod tests {
         net.add_component(Component::Resistor(r4));
         net.add_component(Component::VCCurrentSource(vccs));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
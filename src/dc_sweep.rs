@@ -0,0 +1,313 @@
+use crate::components::{Component, ComponentRef};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use crate::solution::SolutionIndex;
+use crate::sweep::Sweep;
+use nalgebra::base::DVector;
+
+/// Node voltages and branch currents at every point of a DC sweep
+#[derive(Debug, Clone)]
+pub struct DcSweepResult {
+    index: SolutionIndex,
+    values: Vec<f64>,
+    solutions: Vec<DVector<f64>>,
+}
+
+#[allow(dead_code)]
+impl DcSweepResult {
+    /// The swept source values, in sweep order
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The full MNA solution vector at sweep point `point`
+    pub fn solution(&self, point: usize) -> Option<&DVector<f64>> {
+        self.solutions.get(point)
+    }
+
+    /// The voltage of the node called `name` at every sweep point
+    pub fn node_voltage(&self, name: &str) -> Result<Vec<f64>, SimError> {
+        let row = self.index.node_row(name)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+
+    /// The branch current of a voltage source or inductor at every sweep point
+    pub fn branch_current(&self, component: impl Into<ComponentRef>) -> Result<Vec<f64>, SimError> {
+        let row = self.index.branch_row(component)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+}
+
+/// A family of DC sweeps: one inner sweep for each value of an outer parameter, such as the
+/// output curves of a transistor at a set of gate voltages
+#[derive(Debug, Clone)]
+pub struct DcSweep2dResult {
+    outer_values: Vec<f64>,
+    sweeps: Vec<DcSweepResult>,
+}
+
+#[allow(dead_code)]
+impl DcSweep2dResult {
+    /// The outer parameter values, in sweep order
+    pub fn outer_values(&self) -> &[f64] {
+        &self.outer_values
+    }
+
+    /// The inner source values, shared by every outer point
+    pub fn inner_values(&self) -> &[f64] {
+        self.sweeps
+            .first()
+            .map(DcSweepResult::values)
+            .unwrap_or(&[])
+    }
+
+    /// The inner sweep run at outer point `outer`
+    pub fn sweep(&self, outer: usize) -> Option<&DcSweepResult> {
+        self.sweeps.get(outer)
+    }
+
+    /// The full MNA solution vector at outer point `outer` and inner point `inner`
+    pub fn solution(&self, outer: usize, inner: usize) -> Option<&DVector<f64>> {
+        self.sweeps
+            .get(outer)
+            .and_then(|sweep| sweep.solution(inner))
+    }
+
+    /// The voltage of the node called `name`, indexed `[outer][inner]`
+    pub fn node_voltage(&self, name: &str) -> Result<Vec<Vec<f64>>, SimError> {
+        self.sweeps
+            .iter()
+            .map(|sweep| sweep.node_voltage(name))
+            .collect()
+    }
+
+    /// The branch current of a voltage source or inductor, indexed `[outer][inner]`
+    pub fn branch_current(
+        &self,
+        component: impl Into<ComponentRef>,
+    ) -> Result<Vec<Vec<f64>>, SimError> {
+        let component = component.into();
+        self.sweeps
+            .iter()
+            .map(|sweep| sweep.branch_current(component.clone()))
+            .collect()
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Solves the operating point with the DC value of the independent voltage or current
+    /// source `source` set to each value of `sweep` in turn. The source only enters the z
+    /// vector, so `a_mat` is factored once and reused for every point.
+    pub fn dc_sweep(
+        &mut self,
+        source: impl Into<ComponentRef>,
+        sweep: &Sweep,
+    ) -> Result<DcSweepResult, SimError> {
+        let source = source.into();
+        let values = sweep.values()?;
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let (direction, base) = self.source_direction(&index, &source)?;
+        let lu = self.factor_a_mat()?;
+
+        let solutions = values
+            .iter()
+            .map(|value| {
+                let z_mat = &*self.z_mat + &direction * (value - base);
+                lu.solve(&z_mat)
+                    .expect("the factorization was checked for singularity")
+                    .column(0)
+                    .into_owned()
+            })
+            .collect();
+        Ok(DcSweepResult {
+            index,
+            values,
+            solutions,
+        })
+    }
+
+    /// Runs a DC sweep of `source` over `inner` for each value of `outer` over `outer_sweep`.
+    /// A second source is swept by passing `Parameter::SourceValue`. The netlist is restored
+    /// once the sweep finishes.
+    pub fn dc_sweep_2d(
+        &mut self,
+        source: impl Into<ComponentRef>,
+        inner: &Sweep,
+        outer: &Parameter,
+        outer_sweep: &Sweep,
+    ) -> Result<DcSweep2dResult, SimError> {
+        let source = source.into();
+        let outer_values = outer_sweep.values()?;
+        let sweeps = outer_values
+            .iter()
+            .map(|&value| {
+                self.with_parameter(outer, value, |net| net.dc_sweep(source.clone(), inner))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DcSweep2dResult {
+            outer_values,
+            sweeps,
+        })
+    }
+
+    /// How the z vector changes per unit change of an independent source's value, and the value
+    /// the source contributes to the assembled z vector
+    pub(crate) fn source_direction(
+        &self,
+        index: &SolutionIndex,
+        source: &ComponentRef,
+    ) -> Result<(DVector<f64>, f64), SimError> {
+        let mut direction = DVector::<f64>::zeros(self.z_mat.nrows());
+        let component = self
+            .find_component(source)
+            .and_then(|handle| self.component(handle));
+        let base = match component {
+            Some(Component::IVoltageSource(vs)) => {
+                direction[index.aux_row(vs.source_num)] = 1.0;
+                vs.voltage_at(0.0)
+            }
+            Some(Component::ICurrentSource(is)) => {
+                if is.source_node != 0 {
+                    direction[index.row_of_id(is.source_node)?] = -1.0;
+                }
+                if is.sink_node != 0 {
+                    direction[index.row_of_id(is.sink_node)?] = 1.0;
+                }
+                is.current_at(0.0)
+            }
+            _ => return Err(SimError::NotAnIndependentSource(source.clone())),
+        };
+        Ok((direction, base))
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn voltage_source_sweep() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 3.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 1e3);
+        let i1 = independent_current_source::ICurrentSource::new(0, 2, 1e-3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_named_component("I1", Component::ICurrentSource(i1))
+            .expect("new name");
+
+        let sweep = Sweep::Linear {
+            start: -2.0,
+            stop: 2.0,
+            points: 5,
+        };
+        let result = net.dc_sweep("V1", &sweep).expect("sweep should run");
+        assert_eq!(result.values(), &[-2.0, -1.0, 0.0, 1.0, 2.0]);
+        // Superposition: V(2) = V1/2 + I1 * 500
+        let v2 = result.node_voltage("2").unwrap();
+        let i_v1 = result.branch_current("V1").unwrap();
+        for ((value, v), i) in result.values().iter().zip(v2).zip(i_v1) {
+            assert_float_absolute_eq!(v, value / 2.0 + 0.5, 1e-12);
+            assert_float_absolute_eq!(i, -(value - v) / 1e3, 1e-12);
+        }
+
+        // The netlist itself is unchanged by the sweep
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 2.0);
+    }
+
+    #[test]
+    fn current_source_sweep() {
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 2.0);
+        let i1 = net.add_component(Component::ICurrentSource(i1));
+        let r1 = net.add_component(Component::Resistor(r1));
+
+        let sweep = Sweep::Decade {
+            start: 1e-3,
+            stop: 1.0,
+            points_per_decade: 1,
+        };
+        let result = net.dc_sweep(i1, &sweep).expect("sweep should run");
+        let v1 = result.node_voltage("1").unwrap();
+        for (value, v) in result.values().iter().zip(v1) {
+            assert_float_relative_eq!(v, 2.0 * value);
+        }
+
+        let result = net
+            .dc_sweep(i1, &Sweep::List(vec![5.0, -1.0]))
+            .expect("sweep should run");
+        assert_eq!(result.node_voltage("1").unwrap(), vec![10.0, -2.0]);
+
+        assert_eq!(
+            net.dc_sweep(r1, &sweep).unwrap_err(),
+            SimError::NotAnIndependentSource(r1.into())
+        );
+    }
+
+    #[test]
+    fn nested_sweeps() {
+        // V(2) = V1 * R2 / (R1 + R2) + I1 * R1 || R2
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 0.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 1e3);
+        let i1 = independent_current_source::ICurrentSource::new(0, 2, 0.0);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        let r2 = net.add_component(Component::Resistor(r2));
+        net.add_named_component("I1", Component::ICurrentSource(i1))
+            .expect("new name");
+
+        let inner = Sweep::Linear {
+            start: 0.0,
+            stop: 2.0,
+            points: 3,
+        };
+        let outer = Sweep::List(vec![0.0, 1e-3]);
+        let result = net
+            .dc_sweep_2d("V1", &inner, &Parameter::SourceValue("I1".into()), &outer)
+            .expect("sweep should run");
+        assert_eq!(result.outer_values(), &[0.0, 1e-3]);
+        assert_eq!(result.inner_values(), &[0.0, 1.0, 2.0]);
+        let v2 = result.node_voltage("2").unwrap();
+        for (current, row) in result.outer_values().iter().zip(&v2) {
+            for (voltage, v) in result.inner_values().iter().zip(row) {
+                assert_float_absolute_eq!(*v, voltage / 2.0 + current * 500.0, 1e-12);
+            }
+        }
+        assert_eq!(result.branch_current("V1").unwrap().len(), 2);
+        assert_eq!(result.solution(1, 2).map(|x| x[1]), Some(v2[1][2]));
+
+        // Any parameter can be the outer sweep
+        let resistance = Parameter::Resistance(r2.into());
+        let result = net
+            .dc_sweep_2d("V1", &inner, &resistance, &Sweep::List(vec![1e3, 3e3]))
+            .expect("sweep should run");
+        assert_float_relative_eq!(result.node_voltage("2").unwrap()[1][2], 1.5);
+        assert_eq!(net.parameter(&resistance), Ok(1e3));
+        assert_eq!(net.parameter(&Parameter::SourceValue("I1".into())), Ok(0.0));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::components::ComponentRef;
+use crate::parameter::Parameter;
+use crate::singularity::SingularityReport;
+use crate::validation::Diagnostic;
+use std::fmt;
+
+/// Reasons a netlist could not be assembled or solved
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimError {
+    /// `solve_dc_mna` was called before `initialize_dc_mna`, or the netlist changed in between
+    Uninitialized,
+    /// Results were requested before a successful solve
+    NoSolution,
+    /// The direct MNA solve only handles linear components
+    NonlinearCircuit,
+    /// The MNA matrix could not be factored, e.g. because of a floating node or conflicting
+    /// dependent sources
+    SingularMatrix(SingularityReport),
+    /// A node name or id that does not appear in the netlist
+    InvalidNode(String),
+    /// A reference to a component that does not exist or carries no branch current
+    InvalidSourceReference(ComponentRef),
+    /// A reference to a component that does not exist or is not an independent voltage or
+    /// current source
+    NotAnIndependentSource(ComponentRef),
+    /// A parameter whose component does not exist or has no property of that kind
+    InvalidParameter(Parameter),
+    /// A stamp addressed auxiliary variable `index` (1-based) of only `num_aux_variables`
+    SourceIndexOutOfRange {
+        index: usize,
+        num_aux_variables: usize,
+    },
+    /// `add_named_component` was given a name that is already in use
+    DuplicateComponentName(String),
+    /// `Netlist::validate` found structural problems with the circuit
+    Topology(Vec<Diagnostic>),
+    /// Analysis settings that cannot be simulated, e.g. a non-positive timestep
+    InvalidAnalysis(String),
+    /// The adaptive transient control could not meet its tolerances without the step
+    /// collapsing, at the given time
+    TimestepTooSmall { time: f64 },
+    /// The small-signal system has no unique solution at this frequency, e.g. an undamped LC
+    /// tank at resonance
+    SingularAtFrequency(f64),
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::Uninitialized => write!(f, "MNA system has not been initialized"),
+            SimError::NoSolution => write!(f, "MNA system has not been solved"),
+            SimError::NonlinearCircuit => {
+                write!(f, "circuit contains nonlinear components")
+            }
+            SimError::SingularMatrix(report) => write!(f, "MNA matrix is singular: {}", report),
+            SimError::InvalidNode(node) => write!(f, "no node named {} in the netlist", node),
+            SimError::InvalidSourceReference(source) => {
+                write!(
+                    f,
+                    "{:?} does not refer to a source with a branch current",
+                    source
+                )
+            }
+            SimError::NotAnIndependentSource(source) => {
+                write!(f, "{:?} does not refer to an independent source", source)
+            }
+            SimError::InvalidParameter(parameter) => {
+                write!(f, "{:?} is not a parameter of the netlist", parameter)
+            }
+            SimError::SourceIndexOutOfRange {
+                index,
+                num_aux_variables,
+            } => write!(
+                f,
+                "source index {} is out of range for {} auxiliary variables",
+                index, num_aux_variables
+            ),
+            SimError::DuplicateComponentName(name) => {
+                write!(f, "component name {} is already in use", name)
+            }
+            SimError::Topology(diagnostics) => {
+                write!(f, "invalid circuit topology")?;
+                for diagnostic in diagnostics {
+                    write!(f, "; {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            SimError::InvalidAnalysis(reason) => write!(f, "invalid analysis: {}", reason),
+            SimError::TimestepTooSmall { time } => {
+                write!(f, "timestep too small at t={}", time)
+            }
+            SimError::SingularAtFrequency(frequency) => {
+                write!(f, "small-signal matrix is singular at {} Hz", frequency)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
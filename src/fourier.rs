@@ -0,0 +1,419 @@
+use crate::error::SimError;
+use crate::solution::Output;
+use crate::transient::TransientResult;
+use nalgebra::{Complex, ComplexField};
+use std::f64::consts::PI;
+
+/// Samples per period of the fundamental when a transient result is resampled for `fourier`,
+/// before any increase needed to resolve the requested harmonics
+const FOURIER_POINTS_PER_PERIOD: usize = 256;
+
+/// The discrete Fourier transform X_k = sum_n x_n e^(-2 pi i k n / N) of `input`. Power-of-two
+/// lengths use radix-2 Cooley-Tukey; any other length is reduced to a power-of-two
+/// convolution by Bluestein's algorithm, so every length is O(N log N).
+pub fn fft(input: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = input.len();
+    if n.is_power_of_two() || n <= 1 {
+        let mut data = input.to_vec();
+        radix2(&mut data, false);
+        data
+    } else {
+        bluestein(input)
+    }
+}
+
+/// The inverse of `fft`, including the 1/N scaling
+#[allow(dead_code)]
+pub fn ifft(input: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let scale = 1.0 / input.len() as f64;
+    let conjugated: Vec<_> = input.iter().map(|x| x.conj()).collect();
+    fft(&conjugated)
+        .into_iter()
+        .map(|x| x.conj() * scale)
+        .collect()
+}
+
+/// In-place iterative radix-2 transform, unscaled. The length must be a power of two.
+fn radix2(data: &mut [Complex<f64>], inverse: bool) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+    let bits = n.trailing_zeros();
+    for idx in 0..n {
+        let reversed = idx.reverse_bits() >> (usize::BITS - bits);
+        if idx < reversed {
+            data.swap(idx, reversed);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let twiddle = Complex::new((angle * k as f64).cos(), (angle * k as f64).sin());
+                let even = data[start + k];
+                let odd = data[start + k + len / 2] * twiddle;
+                data[start + k] = even + odd;
+                data[start + k + len / 2] = even - odd;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Bluestein's chirp-z transform: X_k = w_k sum_n (x_n w_n) conj(w_(k-n)) with
+/// w_k = e^(-i pi k^2 / N), a convolution that a power-of-two FFT can evaluate
+fn bluestein(input: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = input.len();
+    let m = (2 * n - 1).next_power_of_two();
+    // k^2 is reduced modulo 2N first, since the chirp has that period and k^2 loses precision
+    let chirp: Vec<Complex<f64>> = (0..n)
+        .map(|k| {
+            let angle = -PI * ((k * k) % (2 * n)) as f64 / n as f64;
+            Complex::new(angle.cos(), angle.sin())
+        })
+        .collect();
+
+    let mut a = vec![Complex::new(0.0, 0.0); m];
+    for (k, (x, w)) in input.iter().zip(&chirp).enumerate() {
+        a[k] = x * w;
+    }
+    let mut b = vec![Complex::new(0.0, 0.0); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+    radix2(&mut a, false);
+    radix2(&mut b, false);
+    let mut product: Vec<_> = a.iter().zip(&b).map(|(x, y)| x * y).collect();
+    radix2(&mut product, true);
+    let scale = 1.0 / m as f64;
+    (0..n).map(|k| product[k] * chirp[k] * scale).collect()
+}
+
+/// Tapers applied to a record before transforming it, trading frequency resolution for less
+/// leakage from components that do not complete a whole number of cycles
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Window {
+    #[default]
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+#[allow(dead_code)]
+impl Window {
+    /// The periodic form of the window for an `n`-point record
+    pub fn coefficients(&self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|idx| {
+                let x = 2.0 * PI * idx as f64 / n as f64;
+                match self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * x.cos(),
+                    Window::Hamming => 0.54 - 0.46 * x.cos(),
+                    Window::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The single-sided spectrum of a real signal, from DC to the Nyquist frequency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    pub frequencies: Vec<f64>,
+    /// Amplitude phasors, scaled so that a cosine of amplitude A at a bin frequency gives A
+    /// whatever the window
+    pub amplitudes: Vec<Complex<f64>>,
+}
+
+#[allow(dead_code)]
+impl Spectrum {
+    pub fn magnitudes(&self) -> Vec<f64> {
+        self.amplitudes.iter().map(|a| a.modulus()).collect()
+    }
+
+    /// Phases in degrees, relative to a cosine
+    pub fn phases(&self) -> Vec<f64> {
+        self.amplitudes
+            .iter()
+            .map(|a| a.argument().to_degrees())
+            .collect()
+    }
+}
+
+/// The spectrum of `samples`, taken uniformly at `sample_rate`, after applying `window`
+#[allow(dead_code)]
+pub fn spectrum(samples: &[f64], sample_rate: f64, window: Window) -> Spectrum {
+    let n = samples.len();
+    let coefficients = window.coefficients(n);
+    // Dividing by the window's mean restores the amplitude of a tone
+    let coherent_gain = coefficients.iter().sum::<f64>() / n.max(1) as f64;
+    let windowed: Vec<_> = samples
+        .iter()
+        .zip(&coefficients)
+        .map(|(x, w)| Complex::new(x * w, 0.0))
+        .collect();
+    let bins = fft(&windowed);
+
+    let (frequencies, amplitudes) = (0..(n / 2 + 1).min(n))
+        .map(|k| {
+            // DC and Nyquist have no mirror image in the negative frequencies
+            let single = k == 0 || 2 * k == n;
+            let scale = if single { 1.0 } else { 2.0 } / (n as f64 * coherent_gain);
+            (k as f64 * sample_rate / n as f64, bins[k] * scale)
+        })
+        .unzip();
+    Spectrum {
+        frequencies,
+        amplitudes,
+    }
+}
+
+/// One harmonic of a Fourier analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct Harmonic {
+    /// 1 for the fundamental
+    pub number: usize,
+    pub frequency: f64,
+    pub magnitude: f64,
+    /// In degrees, relative to a cosine
+    pub phase: f64,
+    /// Magnitude relative to the fundamental's
+    pub normalized_magnitude: f64,
+    /// Phase minus the fundamental's phase, in degrees
+    pub normalized_phase: f64,
+}
+
+/// SPICE `.four`-style harmonic content of a periodic waveform
+#[derive(Debug, Clone, PartialEq)]
+pub struct FourierResult {
+    pub fundamental: f64,
+    pub dc: f64,
+    pub harmonics: Vec<Harmonic>,
+    /// Total harmonic distortion, as a fraction of the fundamental: the RMS of harmonics 2 and
+    /// up divided by the fundamental's
+    pub thd: f64,
+}
+
+#[allow(dead_code)]
+impl TransientResult {
+    /// Harmonics 1 to `harmonics` of `output` over the last `periods` periods of
+    /// `fundamental`, with the DC component and THD. The timepoints are linearly interpolated
+    /// onto a uniform grid first, since transient steps need not be uniform.
+    pub fn fourier(
+        &self,
+        output: &Output,
+        fundamental: f64,
+        periods: usize,
+        harmonics: usize,
+    ) -> Result<FourierResult, SimError> {
+        if !fundamental.is_finite() || fundamental <= 0.0 || periods == 0 || harmonics == 0 {
+            return Err(SimError::InvalidAnalysis(
+                "Fourier analysis needs a positive fundamental and at least one period and harmonic"
+                    .to_string(),
+            ));
+        }
+        let values = self.output(output)?;
+        let time = self.time();
+        let period = 1.0 / fundamental;
+        let (Some(&first), Some(&last)) = (time.first(), time.last()) else {
+            return Err(SimError::InvalidAnalysis(
+                "Fourier analysis needs a transient result".to_string(),
+            ));
+        };
+        let start = last - periods as f64 * period;
+        if start < first - period * 1e-9 {
+            return Err(SimError::InvalidAnalysis(format!(
+                "the transient run is shorter than {} period(s) of {} Hz",
+                periods, fundamental
+            )));
+        }
+
+        let points_per_period =
+            FOURIER_POINTS_PER_PERIOD.max((4 * (harmonics + 1)).next_power_of_two());
+        let count = periods * points_per_period;
+        let step = period / points_per_period as f64;
+        let samples: Vec<_> = (0..count)
+            .map(|idx| Complex::new(interpolate(time, &values, start + idx as f64 * step), 0.0))
+            .collect();
+        let bins = fft(&samples);
+
+        // Harmonic h completes h * periods cycles in the record
+        let phasor = |h: usize| bins[h * periods] * (2.0 / count as f64);
+        let first_harmonic = phasor(1);
+        let harmonics: Vec<Harmonic> = (1..=harmonics)
+            .map(|h| {
+                let amplitude = phasor(h);
+                Harmonic {
+                    number: h,
+                    frequency: h as f64 * fundamental,
+                    magnitude: amplitude.modulus(),
+                    phase: amplitude.argument().to_degrees(),
+                    normalized_magnitude: amplitude.modulus() / first_harmonic.modulus(),
+                    normalized_phase: (amplitude.argument() - first_harmonic.argument())
+                        .to_degrees(),
+                }
+            })
+            .collect();
+        let distortion = harmonics
+            .iter()
+            .skip(1)
+            .map(|h| h.magnitude * h.magnitude)
+            .sum::<f64>()
+            .sqrt();
+
+        Ok(FourierResult {
+            fundamental,
+            dc: bins[0].re / count as f64,
+            thd: distortion / first_harmonic.modulus(),
+            harmonics,
+        })
+    }
+}
+
+/// Linear interpolation of `values`, sampled at ascending `time`, at `t`
+fn interpolate(time: &[f64], values: &[f64], t: f64) -> f64 {
+    let after = time.partition_point(|&sample| sample < t);
+    if after == 0 {
+        return values[0];
+    }
+    if after == time.len() {
+        return values[time.len() - 1];
+    }
+    let (t0, t1) = (time[after - 1], time[after]);
+    let (v0, v1) = (values[after - 1], values[after]);
+    v0 + (v1 - v0) * (t - t0) / (t1 - t0)
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::netlist::Netlist;
+    use crate::waveform::Waveform;
+    use assert_float_eq::*;
+
+    #[allow(dead_code)]
+    fn dft(input: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = input.len();
+        (0..n)
+            .map(|k| {
+                input
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, x)| {
+                        let angle = -2.0 * PI * (k * idx) as f64 / n as f64;
+                        x * Complex::new(angle.cos(), angle.sin())
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fft_matches_dft() {
+        for n in [1, 2, 6, 8, 13, 64, 100] {
+            let input: Vec<_> = (0..n)
+                .map(|idx| {
+                    Complex::new(
+                        (idx as f64 * 0.7).sin() + 0.1 * idx as f64,
+                        (idx as f64).cos(),
+                    )
+                })
+                .collect();
+            let fast = fft(&input);
+            for (a, b) in fast.iter().zip(dft(&input)) {
+                assert!((a - b).modulus() < 1e-9, "length {}", n);
+            }
+            for (a, b) in ifft(&fast).iter().zip(&input) {
+                assert!((a - b).modulus() < 1e-12, "length {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn windowed_spectrum() {
+        // 2 sin(2 pi 5 t) + 0.5 sampled 64 times over one second
+        let samples: Vec<f64> = (0..64)
+            .map(|idx| 2.0 * (2.0 * PI * 5.0 * idx as f64 / 64.0).sin() + 0.5)
+            .collect();
+        for window in [
+            Window::Rectangular,
+            Window::Hann,
+            Window::Hamming,
+            Window::Blackman,
+        ] {
+            let result = spectrum(&samples, 64.0, window);
+            assert_eq!(result.frequencies.len(), 33);
+            assert_eq!(result.frequencies[5], 5.0);
+            assert_float_relative_eq!(result.magnitudes()[5], 2.0, 1e-9);
+            assert_float_relative_eq!(result.phases()[5], -90.0, 1e-9);
+            assert_float_relative_eq!(result.amplitudes[0].re, 0.5, 1e-9);
+        }
+        let rectangular = spectrum(&samples, 64.0, Window::Rectangular);
+        assert_float_absolute_eq!(rectangular.magnitudes()[6], 0.0, 1e-12);
+        assert_eq!(
+            spectrum(&samples[..63], 63.0, Window::Hann)
+                .frequencies
+                .len(),
+            32
+        );
+
+        let hann = Window::Hann.coefficients(4);
+        for (w, expected) in hann.iter().zip([0.0, 0.5, 1.0, 0.5]) {
+            assert_float_absolute_eq!(*w, expected, 1e-15);
+        }
+    }
+
+    #[test]
+    fn square_wave_harmonics() {
+        // A +/-1 V square wave at 1 kHz: odd harmonics of 4 / (pi h)
+        let mut net = Netlist::new();
+        let square = Waveform::Pulse {
+            initial: -1.0,
+            pulsed: 1.0,
+            delay: 0.0,
+            rise: 1e-9,
+            fall: 1e-9,
+            width: 0.5e-3 - 1e-9,
+            period: 1e-3,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, square);
+        let r1 = resistor::Resistor::new(1, 0, 1e3);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+
+        let tran = net.transient(3e-3, 1e-6).expect("transient should run");
+        let output = Output::Voltage("1".to_string());
+        let result = tran
+            .fourier(&output, 1e3, 2, 5)
+            .expect("fourier should run");
+        assert_float_absolute_eq!(result.dc, 0.0, 1e-2);
+        assert_eq!(result.harmonics.len(), 5);
+        for harmonic in &result.harmonics {
+            let h = harmonic.number as f64;
+            let expected = if harmonic.number % 2 == 1 {
+                4.0 / (PI * h)
+            } else {
+                0.0
+            };
+            assert_float_absolute_eq!(harmonic.magnitude, expected, 1e-2);
+            assert_eq!(harmonic.frequency, h * 1e3);
+        }
+        assert_float_relative_eq!(result.harmonics[2].normalized_magnitude, 1.0 / 3.0, 1e-2);
+        assert_float_relative_eq!(result.thd, (1.0f64 / 9.0 + 1.0 / 25.0).sqrt(), 1e-2);
+
+        assert!(matches!(
+            tran.fourier(&output, 1e3, 4, 5),
+            Err(SimError::InvalidAnalysis(_))
+        ));
+        assert!(tran.fourier(&output, 0.0, 1, 5).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+/// Numerical integration rule used to discretise the energy-storage elements in time
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntegrationMethod {
+    /// First order and L-stable; damps ringing but smears fast edges
+    BackwardEuler,
+    /// Second order and A-stable; the SPICE default
+    #[default]
+    Trapezoidal,
+    /// Backward differentiation formula of the given order, 1 to 6. Stiffly stable, so it does
+    /// not ring where the trapezoidal rule does. Order 1 is backward Euler.
+    Gear { order: usize },
+}
+
+#[allow(dead_code)]
+impl IntegrationMethod {
+    /// Order of accuracy: the local truncation error is proportional to h^(order + 1)
+    pub fn order(&self) -> usize {
+        match self {
+            IntegrationMethod::BackwardEuler => 1,
+            IntegrationMethod::Trapezoidal => 2,
+            IntegrationMethod::Gear { order } => *order,
+        }
+    }
+
+    /// Magnitude of the constant C in the local truncation error C * h^(k+1) * x^(k+1)
+    pub fn error_constant(&self) -> f64 {
+        match self {
+            IntegrationMethod::BackwardEuler => 1.0 / 2.0,
+            IntegrationMethod::Trapezoidal => 1.0 / 12.0,
+            IntegrationMethod::Gear { order } => GEAR_ERROR_CONSTANTS[order - 1],
+        }
+    }
+
+    /// The highest order for which the backward differentiation formulas are stable
+    pub const MAX_GEAR_ORDER: usize = 6;
+}
+
+/// Error constants of the backward differentiation formulas of orders 1 to 6
+const GEAR_ERROR_CONSTANTS: [f64; IntegrationMethod::MAX_GEAR_ORDER] = [
+    1.0 / 2.0,
+    2.0 / 9.0,
+    3.0 / 22.0,
+    12.0 / 125.0,
+    10.0 / 137.0,
+    20.0 / 343.0,
+];
+
+/// Norton equivalent of an energy-storage element over one timestep: a conductance in parallel
+/// with a history current source, so that the element current is
+/// `conductance * v - current`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Companion {
+    pub conductance: f64,
+    pub current: f64,
+}
+
+#[allow(dead_code)]
+impl Companion {
+    /// Companion model of `i = k * dx/dt`, where `x_prev` and `i_prev` are the state and
+    /// current at the previous timepoint and `h` is the step to the new one. With only one past
+    /// point to go on, Gear is taken at order 1; use `from_history` for higher orders.
+    pub fn new(method: IntegrationMethod, k: f64, h: f64, x_prev: f64, i_prev: f64) -> Self {
+        match method {
+            // i_n = k/h * (x_n - x_{n-1})
+            IntegrationMethod::BackwardEuler | IntegrationMethod::Gear { .. } => Self {
+                conductance: k / h,
+                current: k / h * x_prev,
+            },
+            // i_n = 2k/h * (x_n - x_{n-1}) - i_{n-1}
+            IntegrationMethod::Trapezoidal => Self {
+                conductance: 2.0 * k / h,
+                current: 2.0 * k / h * x_prev + i_prev,
+            },
+        }
+    }
+
+    /// Companion model of `i = k * dx/dt` with the derivative taken as a weighted sum of the new
+    /// state and the past states `x_past`, newest first. `coefficients` are the weights from
+    /// `gear_coefficients`, the first applying to the new state.
+    pub fn from_history(k: f64, coefficients: &[f64], x_past: &[f64]) -> Self {
+        let history: f64 = coefficients[1..]
+            .iter()
+            .zip(x_past)
+            .map(|(alpha, x)| alpha * x)
+            .sum();
+        Self {
+            conductance: k * coefficients[0],
+            current: -k * history,
+        }
+    }
+}
+
+/// Weights of the variable-step backward differentiation formula: the derivative at `times[0]`
+/// of the polynomial through the states at every point of `times`, which are newest first.
+/// The order is one less than the number of points.
+pub fn gear_coefficients(times: &[f64]) -> Vec<f64> {
+    let t0 = times[0];
+    (0..times.len())
+        .map(|j| {
+            if j == 0 {
+                return times[1..].iter().map(|tm| 1.0 / (t0 - tm)).sum();
+            }
+            let mut numerator = 1.0;
+            let mut denominator = 1.0;
+            for (m, tm) in times.iter().enumerate() {
+                if m != j {
+                    denominator *= times[j] - tm;
+                    if m != 0 {
+                        numerator *= t0 - tm;
+                    }
+                }
+            }
+            numerator / denominator
+        })
+        .collect()
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn backward_euler() {
+        let companion = Companion::new(IntegrationMethod::BackwardEuler, 2.0, 0.5, 3.0, 1.0);
+        assert_float_relative_eq!(companion.conductance, 4.0);
+        assert_float_relative_eq!(companion.current, 12.0);
+    }
+
+    #[test]
+    fn trapezoidal() {
+        let companion = Companion::new(IntegrationMethod::Trapezoidal, 2.0, 0.5, 3.0, 1.0);
+        assert_float_relative_eq!(companion.conductance, 8.0);
+        assert_float_relative_eq!(companion.current, 25.0);
+    }
+
+    #[test]
+    fn gear_constant_step() {
+        // BDF2 with a constant step: (3x_n - 4x_{n-1} + x_{n-2}) / 2h
+        let coefficients = gear_coefficients(&[1.0, 0.5, 0.0]);
+        assert_float_relative_eq!(coefficients[0], 3.0);
+        assert_float_relative_eq!(coefficients[1], -4.0);
+        assert_float_relative_eq!(coefficients[2], 1.0);
+
+        let companion = Companion::from_history(2.0, &coefficients, &[3.0, 1.0]);
+        assert_float_relative_eq!(companion.conductance, 6.0);
+        assert_float_relative_eq!(companion.current, 22.0);
+    }
+
+    #[test]
+    fn gear_is_exact_for_polynomials() {
+        // The order-k formula differentiates polynomials of degree k exactly, on any grid
+        let times = [1.3, 1.0, 0.6, 0.5, 0.1];
+        let coefficients = gear_coefficients(&times);
+        let derivative: f64 = coefficients
+            .iter()
+            .zip(times)
+            .map(|(alpha, t)| alpha * (t * t * t * t - 2.0 * t))
+            .sum();
+        assert_float_relative_eq!(derivative, 4.0 * 1.3f64.powi(3) - 2.0, 1e-9);
+    }
+
+    #[test]
+    fn gear_error_constants() {
+        let expected = [
+            1.0 / 2.0,
+            2.0 / 9.0,
+            3.0 / 22.0,
+            12.0 / 125.0,
+            10.0 / 137.0,
+            20.0 / 343.0,
+        ];
+        for (order, constant) in (1..=IntegrationMethod::MAX_GEAR_ORDER).zip(expected) {
+            let method = IntegrationMethod::Gear { order };
+            assert_float_relative_eq!(method.error_constant(), constant);
+
+            // The formula's residual on t^(k+1)/(k+1)! with unit steps, whose derivative is 0
+            // at t = 0, scaled to an error in the new state
+            let times: Vec<f64> = (0..=order).map(|j| -(j as f64)).collect();
+            let coefficients = gear_coefficients(&times);
+            let factorial: f64 = (1..=order + 1).map(|j| j as f64).product();
+            let residual: f64 = coefficients
+                .iter()
+                .zip(&times)
+                .map(|(alpha, t)| alpha * t.powi(order as i32 + 1) / factorial)
+                .sum();
+            assert_float_relative_eq!((residual / coefficients[0]).abs(), constant, 1e-9);
+        }
+        assert_eq!(IntegrationMethod::BackwardEuler.error_constant(), 0.5);
+    }
+}
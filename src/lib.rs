@@ -2,8 +2,30 @@ pub fn add(left: usize, right: usize) -> usize {
     left + right
 }
 
+mod ac;
 mod components;
+mod dc_sweep;
+mod error;
+mod fourier;
+mod integration;
+mod monte_carlo;
 mod netlist;
+mod node_table;
+mod noise;
+mod parameter;
+mod parser;
+mod pole_zero;
+mod sensitivity;
+mod singularity;
+mod solution;
+mod step;
+mod sweep;
+mod tolerance;
+mod transfer_function;
+mod transient;
+mod validation;
+mod waveform;
+mod worst_case;
 
 use crate::components::Stamp;
 pub trait DCComponent {
@@ -0,0 +1,346 @@
+use crate::components::ComponentRef;
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use crate::solution::SolutionIndex;
+use crate::tolerance::{sample_parameters, Rng};
+use nalgebra::base::DVector;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloOptions {
+    pub trials: usize,
+    /// Runs with the same seed and netlist draw the same component values
+    pub seed: u64,
+    /// Number of histogram bins in the reported statistics
+    pub bins: usize,
+}
+
+impl Default for MonteCarloOptions {
+    fn default() -> Self {
+        Self {
+            trials: 100,
+            seed: 0,
+            bins: 10,
+        }
+    }
+}
+
+/// Equal-width bins spanning the smallest to the largest sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub low: f64,
+    pub high: f64,
+    pub counts: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl Histogram {
+    /// The `counts.len() + 1` bin edges from `low` to `high`
+    pub fn edges(&self) -> Vec<f64> {
+        let bins = self.counts.len();
+        (0..=bins)
+            .map(|edge| self.low + (self.high - self.low) * edge as f64 / bins as f64)
+            .collect()
+    }
+}
+
+/// Summary of one measured quantity over all trials
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub mean: f64,
+    /// Sample standard deviation
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub histogram: Histogram,
+}
+
+#[allow(dead_code)]
+impl Statistics {
+    /// Returns None when there are no samples
+    pub fn from_samples(samples: &[f64], bins: usize) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / count;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1.0).max(1.0);
+        let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        let bins = bins.max(1);
+        let mut counts = vec![0; bins];
+        let width = (max - min) / bins as f64;
+        for sample in samples {
+            let bin = if width > 0.0 {
+                (((sample - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+
+        Some(Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min,
+            max,
+            histogram: Histogram {
+                low: min,
+                high: max,
+                counts,
+            },
+        })
+    }
+}
+
+/// The result of every trial of a Monte Carlo run, with the component values it used
+#[derive(Debug, Clone)]
+pub struct MonteCarloResult<T> {
+    bins: usize,
+    values: Vec<Vec<(Parameter, f64)>>,
+    results: Vec<T>,
+}
+
+#[allow(dead_code)]
+impl<T> MonteCarloResult<T> {
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The analysis results, in trial order
+    pub fn results(&self) -> &[T] {
+        &self.results
+    }
+
+    /// The toleranced parameter values drawn for trial `trial`
+    pub fn values(&self, trial: usize) -> Option<&[(Parameter, f64)]> {
+        self.values.get(trial).map(Vec::as_slice)
+    }
+
+    /// Statistics of `measure` applied to every trial's result
+    pub fn statistics(
+        &self,
+        measure: impl Fn(&T) -> Result<f64, SimError>,
+    ) -> Result<Statistics, SimError> {
+        let samples = self
+            .results
+            .iter()
+            .map(measure)
+            .collect::<Result<Vec<_>, _>>()?;
+        Statistics::from_samples(&samples, self.bins).ok_or_else(|| {
+            SimError::InvalidAnalysis("a Monte Carlo run needs at least one trial".to_string())
+        })
+    }
+}
+
+/// Operating points of every trial of a DC Monte Carlo run
+#[derive(Debug, Clone)]
+pub struct DcMonteCarloResult {
+    index: SolutionIndex,
+    trials: MonteCarloResult<DVector<f64>>,
+}
+
+#[allow(dead_code)]
+impl DcMonteCarloResult {
+    /// The full MNA solution vector of every trial
+    pub fn trials(&self) -> &MonteCarloResult<DVector<f64>> {
+        &self.trials
+    }
+
+    /// The voltage of the node called `name` in every trial
+    pub fn node_voltage(&self, name: &str) -> Result<Vec<f64>, SimError> {
+        let row = self.index.node_row(name)?;
+        Ok(self.trials.results.iter().map(|x| x[row]).collect())
+    }
+
+    pub fn node_statistics(&self, name: &str) -> Result<Statistics, SimError> {
+        let row = self.index.node_row(name)?;
+        self.trials.statistics(|x| Ok(x[row]))
+    }
+
+    /// Statistics of the branch current of a voltage source or inductor
+    pub fn branch_statistics(
+        &self,
+        component: impl Into<ComponentRef>,
+    ) -> Result<Statistics, SimError> {
+        let row = self.index.branch_row(component)?;
+        self.trials.statistics(|x| Ok(x[row]))
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Runs `analysis` once per trial, with every toleranced component drawn afresh from its
+    /// distribution. The netlist is restored afterwards, and the first failing trial aborts the
+    /// run with its error.
+    pub fn monte_carlo<T>(
+        &mut self,
+        options: &MonteCarloOptions,
+        mut analysis: impl FnMut(&mut Netlist) -> Result<T, SimError>,
+    ) -> Result<MonteCarloResult<T>, SimError> {
+        let parameters = self.toleranced_parameters();
+        let mut rng = Rng::new(options.seed);
+        let mut values = Vec::with_capacity(options.trials);
+        let mut results = Vec::with_capacity(options.trials);
+        for _ in 0..options.trials {
+            let trial = sample_parameters(&parameters, &mut rng);
+            results.push(self.with_parameters(&trial, &mut analysis)?);
+            values.push(trial);
+        }
+        Ok(MonteCarloResult {
+            bins: options.bins,
+            values,
+            results,
+        })
+    }
+
+    /// Monte Carlo analysis of the DC operating point
+    pub fn monte_carlo_dc(
+        &mut self,
+        options: &MonteCarloOptions,
+    ) -> Result<DcMonteCarloResult, SimError> {
+        self.initialize_dc_mna()?;
+        let index = self.solution_index()?;
+        let trials = self.monte_carlo(options, |net| {
+            net.initialize_dc_mna()?;
+            net.solve_dc_mna()?;
+            Ok(net.x_mat.column(0).into_owned())
+        })?;
+        Ok(DcMonteCarloResult { index, trials })
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::tolerance::Tolerance;
+    use crate::waveform::Waveform;
+    use assert_float_eq::*;
+
+    #[test]
+    fn statistics() {
+        let stats = Statistics::from_samples(&[1.0, 2.0, 3.0, 4.0, 10.0], 3).unwrap();
+        assert_float_relative_eq!(stats.mean, 4.0);
+        assert_float_relative_eq!(stats.std_dev, 12.5f64.sqrt());
+        assert_eq!((stats.min, stats.max), (1.0, 10.0));
+        assert_eq!(stats.histogram.counts, vec![3, 1, 1]);
+        assert_eq!(stats.histogram.edges(), vec![1.0, 4.0, 7.0, 10.0]);
+
+        let constant = Statistics::from_samples(&[2.0, 2.0], 4).unwrap();
+        assert_eq!(constant.std_dev, 0.0);
+        assert_eq!(constant.histogram.counts, vec![2, 0, 0, 0]);
+        assert_eq!(Statistics::from_samples(&[], 4), None);
+    }
+
+    #[test]
+    fn divider_spread() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 10.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3).with_tolerance(Tolerance::uniform(0.05));
+        let r2 = resistor::Resistor::new(2, 0, 1e3).with_tolerance(Tolerance::gaussian(0.05));
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        let r1 = net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+
+        let options = MonteCarloOptions {
+            trials: 500,
+            seed: 42,
+            bins: 8,
+        };
+        let result = net.monte_carlo_dc(&options).expect("trials should run");
+        let stats = result.node_statistics("2").unwrap();
+        assert_eq!(result.trials().len(), 500);
+        assert_eq!(stats.histogram.counts.iter().sum::<usize>(), 500);
+        assert_float_absolute_eq!(stats.mean, 5.0, 0.02);
+        // Both resistors stay within 5%, so the output stays within 2 * 2.5% of nominal
+        assert!(stats.min > 5.0 * (1.0 - 0.05) - 1e-9 && stats.max < 5.0 * 1.05 + 1e-9);
+        assert!(stats.std_dev > 0.01 && stats.std_dev < 0.1);
+        let current = result.branch_statistics("V1").unwrap();
+        assert!(current.max < 0.0);
+
+        // The drawn values explain each trial's output
+        let trial = result.trials().values(3).unwrap();
+        assert_eq!(trial[0].0, Parameter::Resistance(r1.into()));
+        let v2 = 10.0 * trial[1].1 / (trial[0].1 + trial[1].1);
+        assert_float_relative_eq!(result.node_voltage("2").unwrap()[3], v2);
+
+        // Same seed, same trials; the netlist keeps its nominal values
+        let again = net.monte_carlo_dc(&options).expect("trials should run");
+        assert_eq!(again.node_statistics("2").unwrap(), stats);
+        assert_eq!(net.parameter(&Parameter::Resistance(r1.into())), Ok(1e3));
+    }
+
+    #[test]
+    fn any_analysis() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0)
+            .with_tolerance(Tolerance::uniform(0.1));
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+
+        let options = MonteCarloOptions {
+            trials: 20,
+            ..Default::default()
+        };
+        let result = net
+            .monte_carlo(&options, |net| net.transient(1e-3, 1e-3))
+            .expect("trials should run");
+        let stats = result
+            .statistics(|tran| Ok(tran.node_voltage("1")?[1]))
+            .unwrap();
+        assert!(stats.min >= 0.9 && stats.max <= 1.1);
+        assert!(result
+            .statistics(|tran| Ok(tran.node_voltage("9")?[0]))
+            .is_err());
+    }
+
+    #[test]
+    fn time_varying_source() {
+        let mut net = Netlist::new();
+        let sin = Waveform::Sin {
+            offset: 0.5,
+            amplitude: 1.0,
+            frequency: 1e3,
+            delay: 0.0,
+            damping: 0.0,
+            phase: 0.0,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, sin.clone())
+            .with_tolerance(Tolerance::uniform(0.1));
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+
+        let options = MonteCarloOptions {
+            trials: 20,
+            ..Default::default()
+        };
+        let result = net
+            .monte_carlo(&options, |net| net.transient(1e-3, 1e-5))
+            .expect("trials should run");
+        let mut peaks = vec![];
+        for tran in result.results() {
+            // Each trial is the nominal sinusoid scaled as a whole, offset and amplitude alike
+            let voltages = tran.node_voltage("1").unwrap();
+            let scale = voltages[0] / 0.5;
+            assert!((0.9..=1.1).contains(&scale));
+            for (&t, &v) in tran.time().iter().zip(&voltages) {
+                assert_float_absolute_eq!(v, scale * sin.value(t), 1e-9);
+            }
+            peaks.push(voltages.iter().copied().fold(f64::MIN, f64::max));
+        }
+        let stats = Statistics::from_samples(&peaks, 4).unwrap();
+        assert!(stats.min >= 1.5 * 0.9 - 1e-2 && stats.max <= 1.5 * 1.1);
+        assert!(stats.std_dev > 0.0);
+    }
+}
@@ -1,16 +1,35 @@
 use crate::components::Component;
-use crate::components::Stamp;
+use crate::components::{ComponentHandle, ComponentRef, Stamp};
+use crate::error::SimError;
+use crate::node_table::NodeTable;
+use crate::singularity;
+use crate::solution::SolutionIndex;
 use crate::DCComponent;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use nalgebra::base::DMatrix;
+use nalgebra::base::{DMatrix, DVector};
+use nalgebra::linalg::FullPivLU;
+use nalgebra::Dyn;
+
+/// SPICE's nominal temperature, in degrees Celsius
+pub const DEFAULT_TEMPERATURE: f64 = 27.0;
 
 #[allow(dead_code)]
+#[derive(Debug)]
 pub struct Netlist {
     component_list: Vec<Component>,
+    component_names: HashMap<String, ComponentHandle>,
+    /// Node voltages pinned at t=0 (`.ic`), by node id
+    initial_conditions: BTreeMap<u64, f64>,
+    /// Starting guesses for the operating point (`.nodeset`), by node id
+    nodesets: BTreeMap<u64, f64>,
+    nodes: NodeTable,
     initialized: bool,
     x_mat_valid: bool,
     num_nodes: Option<usize>,
+    /// Circuit temperature in degrees Celsius, used by temperature-dependent analyses such as
+    /// noise
+    temperature: f64,
     // TODO: evaluate possibilities for Option(x_mat) instead
     //   Pros: cleaner representation, more idiomatic
     //   Cons: more frequent allocation?
@@ -30,37 +49,221 @@ impl Netlist {
         }
     }
 
-    pub fn add_component(&mut self, new_component: Component) {
+    /// Adds a component and returns a handle to it. Components that carry a branch current
+    /// (voltage sources) are assigned the next free auxiliary variable here, so their
+    /// `source_num` need not be chosen by the caller.
+    pub fn add_component(&mut self, mut new_component: Component) -> ComponentHandle {
+        if new_component.source_num().is_some() {
+            new_component.set_source_num(self.num_aux_variables() as u64 + 1);
+        }
         self.component_list.push(new_component);
+        self.initialized = false;
+        self.x_mat_valid = false;
+        ComponentHandle(self.component_list.len() - 1)
+    }
+
+    /// Adds a component that can later be referred to by `name`, e.g. as the controlling source
+    /// of a current-controlled source
+    pub fn add_named_component(
+        &mut self,
+        name: &str,
+        new_component: Component,
+    ) -> Result<ComponentHandle, SimError> {
+        if self.component_names.contains_key(name) {
+            return Err(SimError::DuplicateComponentName(name.to_string()));
+        }
+        let handle = self.add_component(new_component);
+        self.component_names.insert(name.to_string(), handle);
+        Ok(handle)
+    }
+
+    /// Looks up the handle of a component, checking that it exists in this netlist
+    pub fn find_component(&self, component: &ComponentRef) -> Option<ComponentHandle> {
+        match component {
+            ComponentRef::Handle(handle) if handle.0 < self.component_list.len() => Some(*handle),
+            ComponentRef::Handle(_) => None,
+            ComponentRef::Name(name) => self.component_names.get(name).copied(),
+        }
+    }
+
+    pub fn component(&self, handle: ComponentHandle) -> Option<&Component> {
+        self.component_list.get(handle.0)
+    }
+
+    /// Mutable access to a component. The MNA system is invalidated, since the change may
+    /// affect any stamp.
+    pub(crate) fn component_mut(&mut self, handle: ComponentHandle) -> Option<&mut Component> {
+        self.initialized = false;
+        self.x_mat_valid = false;
+        self.component_list.get_mut(handle.0)
+    }
+
+    pub(crate) fn components(&self) -> &[Component] {
+        &self.component_list
+    }
+
+    /// The name a component was added under, or `#<index>` for unnamed components
+    pub fn component_label(&self, handle: ComponentHandle) -> String {
+        self.component_names
+            .iter()
+            .find(|(_, &named)| named == handle)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| format!("#{}", handle.0))
+    }
+
+    /// Pins node `node` to `voltage` at t=0, as SPICE's `.ic`. The transient operating point
+    /// holds pinned nodes at their values, unless a voltage source drives them, and solves
+    /// only for the rest; with `use_initial_conditions` the values set the initial voltage of
+    /// every capacitor that has none of its own.
+    pub fn set_initial_condition(&mut self, node: u64, voltage: f64) {
+        self.initial_conditions.insert(node, voltage);
+    }
+
+    pub fn initial_conditions(&self) -> &BTreeMap<u64, f64> {
+        &self.initial_conditions
+    }
+
+    /// Suggests `voltage` as the starting point for node `node` when solving the operating
+    /// point, as SPICE's `.nodeset`. Unlike an initial condition a nodeset does not constrain
+    /// the solution. Every solve is linear for now and needs no starting point, so nodesets are
+    /// only stored, for a future nonlinear solver to start from.
+    pub fn set_nodeset(&mut self, node: u64, voltage: f64) {
+        self.nodesets.insert(node, voltage);
+    }
+
+    pub fn nodesets(&self) -> &BTreeMap<u64, f64> {
+        &self.nodesets
+    }
+
+    /// Solves the operating point with every node that has an initial condition held at its
+    /// value: the KCL row of a pinned node is replaced by the equation fixing its voltage. As in
+    /// SPICE, a node on a voltage source or on an inductor, which is a short at DC, keeps the
+    /// value the branch gives it and its initial condition is ignored. Replacing its KCL row
+    /// would drop the branch current from the system.
+    pub(crate) fn solve_pinned_dc(&self, index: &SolutionIndex) -> Result<DVector<f64>, SimError> {
+        let branches: Vec<usize> = self
+            .component_list
+            .iter()
+            .filter_map(|component| component.source_num())
+            .map(|source_num| index.aux_row(source_num))
+            .collect();
+        let mut a_mat = (*self.a_mat).clone();
+        let mut z_mat = self.z_mat.column(0).into_owned();
+        for (&node, &voltage) in &self.initial_conditions {
+            let row = index.row_of_id(node)?;
+            // The branch current enters the node's KCL row and the branch equation sets the node
+            let on_branch = branches
+                .iter()
+                .any(|&branch| a_mat[(row, branch)] != 0.0 && a_mat[(branch, row)] != 0.0);
+            if on_branch {
+                continue;
+            }
+            a_mat.row_mut(row).fill(0.0);
+            a_mat[(row, row)] = 1.0;
+            z_mat[row] = voltage;
+        }
+        let lu = a_mat.clone().full_piv_lu();
+        if singularity::is_singular(&lu, &a_mat) {
+            return Err(SimError::SingularMatrix(self.singularity_report(&a_mat)));
+        }
+        Ok(lu
+            .solve(&z_mat)
+            .expect("the factorization was checked for singularity"))
+    }
+
+    /// The circuit temperature in degrees Celsius
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    pub fn set_temperature(&mut self, celsius: f64) {
+        self.temperature = celsius;
+    }
+
+    pub(crate) fn node_table(&self) -> &NodeTable {
+        &self.nodes
+    }
+
+    /// Returns the id of the node called `name` for use in component constructors, allocating a
+    /// new one the first time a name is seen. "0" and "gnd" are ground, and integer names refer to
+    /// the node with that id.
+    pub fn node(&mut self, name: &str) -> u64 {
+        self.nodes.node(name)
     }
 
     pub fn is_linear(&self) -> bool {
         self.component_list.iter().all(|c| c.is_linear())
     }
 
-    pub fn initialize_dc_mna(&mut self) {
+    /// Points every current-controlled source at the branch variable of its controlling source
+    fn resolve_controlling_sources(&mut self) -> Result<(), SimError> {
+        let resolved = self
+            .component_list
+            .iter()
+            .map(|component| {
+                component
+                    .controlling_source()
+                    .map(|control| {
+                        self.find_component(control)
+                            .and_then(|handle| self.component_list[handle.0].source_num())
+                            .ok_or_else(|| SimError::InvalidSourceReference(control.clone()))
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<Option<u64>>, SimError>>()?;
+        for (component, dep_source_num) in self.component_list.iter_mut().zip(resolved) {
+            if let Some(dep_source_num) = dep_source_num {
+                component.set_dep_source_num(dep_source_num);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn initialize_dc_mna(&mut self) -> Result<(), SimError> {
+        self.initialized = false;
+        self.x_mat_valid = false;
+
+        let diagnostics = self.validate();
+        if !diagnostics.is_empty() {
+            return Err(SimError::Topology(diagnostics));
+        }
+        self.resolve_controlling_sources()?;
+
         // Construct A matrix
         // Dimensions must be N+MxN+M, where N is #nodes and M is #ind v sources
-        let n = self.num_nodes();
+        let node_ids = self.node_ids();
+        self.nodes.assign_rows(&node_ids);
+        let n = node_ids.len();
         self.num_nodes = Some(n);
         let m = self.num_aux_variables();
+        // Stamps address nodes by id, which must be mapped onto the dense MNA rows
+        let nodes = &self.nodes;
+        let row = |id: usize| {
+            nodes
+                .row(id as u64)
+                .ok_or_else(|| SimError::InvalidNode(nodes.name_of(id as u64)))
+        };
+        let aux = |index: usize| {
+            if index == 0 || index > m {
+                Err(SimError::SourceIndexOutOfRange {
+                    index,
+                    num_aux_variables: m,
+                })
+            } else {
+                Ok(index - 1)
+            }
+        };
 
-        self.a_mat = Box::new(DMatrix::<f64>::from_element(n + m, n + m, 0.0));
-        self.x_mat = Box::new(DMatrix::<f64>::from_element(n + m, 1, 0.0));
-        self.z_mat = Box::new(DMatrix::<f64>::from_element(n + m, 1, 0.0));
+        *self.a_mat = DMatrix::<f64>::from_element(n + m, n + m, 0.0);
+        *self.x_mat = DMatrix::<f64>::from_element(n + m, 1, 0.0);
+        *self.z_mat = DMatrix::<f64>::from_element(n + m, 1, 0.0);
 
         // Construct G matrix from conductances
         let mut g_mat = DMatrix::<f64>::from_element(n, n, 0.0);
-        eprintln!(
-            "Allocated gmat with shape ({}, {})",
-            g_mat.nrows(),
-            g_mat.ncols()
-        );
         for component in &self.component_list {
             let stamps = component.get_gmat_stamps();
             for Stamp(r, c, val) in stamps {
-                eprintln!("Got gmat stamp: {:?}", Stamp(r, c, val));
-                let mut cell = g_mat.view_mut((r - 1, c - 1), (1, 1));
+                let mut cell = g_mat.view_mut((row(r)?, row(c)?), (1, 1));
                 cell[(0, 0)] += val;
             }
         }
@@ -69,16 +272,10 @@ impl Netlist {
 
         // Construct B matrix from independent V sources
         let mut b_mat = DMatrix::<f64>::from_element(n, m, 0.0);
-        eprintln!(
-            "Allocated bmat with shape ({}, {})",
-            b_mat.nrows(),
-            b_mat.ncols()
-        );
         for component in &self.component_list {
             let stamps = component.get_bmat_stamps();
             for Stamp(r, c, val) in stamps {
-                eprintln!("Got bmat stamp: {:?}", Stamp(r, c, val));
-                let mut cell = b_mat.view_mut((r - 1, c - 1), (1, 1));
+                let mut cell = b_mat.view_mut((row(r)?, aux(c)?), (1, 1));
                 cell[(0, 0)] += val;
             }
         }
@@ -88,16 +285,10 @@ impl Netlist {
         // Construct C matrix from B matrix transpose, handling dependent sources
         // TODO: handle dependent sources
         let mut c_mat = DMatrix::<f64>::from_element(m, n, 0.0);
-        eprintln!(
-            "Allocated cmat with shape ({}, {})",
-            c_mat.nrows(),
-            c_mat.ncols()
-        );
         for component in &self.component_list {
             let stamps = component.get_cmat_stamps();
             for Stamp(r, c, val) in stamps {
-                eprintln!("Got cmat stamp: {:?}", Stamp(r, c, val));
-                let mut cell = c_mat.view_mut((r - 1, c - 1), (1, 1));
+                let mut cell = c_mat.view_mut((aux(r)?, row(c)?), (1, 1));
                 cell[(0, 0)] += val;
             }
         }
@@ -107,23 +298,27 @@ impl Netlist {
         // Construct D matrix from dependent sources
         // TODO: handle dependent sources
         let mut d_mat = DMatrix::<f64>::from_element(m, m, 0.0);
-        eprintln!(
-            "Allocated dmat with shape({}, {})",
-            d_mat.nrows(),
-            d_mat.ncols()
-        );
         for component in &self.component_list {
             let stamps = component.get_dmat_stamps();
             for Stamp(r, c, val) in stamps {
-                eprintln!("Got dmat stamp: {:?}", Stamp(r, c, val));
-                let mut cell = d_mat.view_mut((r - 1, c - 1), (1, 1));
+                let mut cell = d_mat.view_mut((aux(r)?, aux(c)?), (1, 1));
                 cell[(0, 0)] += val;
             }
         }
         let mut d_view = self.a_mat.view_mut((n, n), (m, m));
         d_view += d_mat.view_mut((0, 0), (m, m));
 
-        // Construct Z matrix from independent sources
+        // Construct Z matrix from independent sources, which the operating point sees at t=0
+        let z_mat = self.source_vector(0.0)?;
+        self.z_mat.copy_from(&z_mat);
+
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// The z vector with every independent source evaluated at time `t`. Requires the rows
+    /// assigned by `initialize_dc_mna`.
+    pub(crate) fn source_vector(&self, t: f64) -> Result<DVector<f64>, SimError> {
         // The z matrix holds our independent voltage and current sources and will be developed as the
         // combination of 2 smaller matrices i and e. It is quite easy to formulate.
         // z = [i; e]
@@ -132,61 +327,84 @@ impl Netlist {
         // • the i matrix is 1×N and contains the sum of the currents through the passive elements into
         //   the corresponding node (either zero, or the sum of independent current sources)
         // • the e matrix is 1×M and holds the values of the independent voltage source
-        let mut z_mat = DMatrix::<f64>::from_element(n + m, 1, 0.0);
-        let mut v_stamps: Vec<Stamp> = vec![];
-        let mut i_stamps: Vec<Stamp> = vec![];
+        let n = self.nodes.num_rows();
+        let m = self.num_aux_variables();
+        let mut z_mat = DVector::<f64>::zeros(n + m);
         for component in &self.component_list {
             match component {
                 Component::IVoltageSource(vs) => {
-                    v_stamps.append(&mut vs.get_zmat_stamps());
+                    for Stamp(r, _, val) in vs.get_zmat_stamps_at(t) {
+                        if r == 0 || r > m {
+                            return Err(SimError::SourceIndexOutOfRange {
+                                index: r,
+                                num_aux_variables: m,
+                            });
+                        }
+                        z_mat[n + r - 1] = val;
+                    }
                 }
                 Component::ICurrentSource(is) => {
-                    i_stamps.append(&mut is.get_zmat_stamps());
+                    for Stamp(r, _, val) in is.get_zmat_stamps_at(t) {
+                        let row = self
+                            .nodes
+                            .row(r as u64)
+                            .ok_or_else(|| SimError::InvalidNode(self.nodes.name_of(r as u64)))?;
+                        z_mat[row] += val;
+                    }
                 }
                 Component::Resistor(_)
                 | Component::VCCurrentSource(_)
                 | Component::CCCurrentSource(_)
-                | Component::CCVoltageSource(_) => {}
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_)
+                | Component::Capacitor(_)
+                | Component::Inductor(_) => {}
             }
         }
-        for Stamp(r, c, val) in i_stamps {
-            let mut cell = self.z_mat.view_mut((r - 1, c - 1), (1, 1));
-            cell[(0, 0)] = val;
-        }
-        for Stamp(r, c, val) in v_stamps {
-            let mut cell = self.z_mat.view_mut((n + r - 1, c - 1), (1, 1));
-            cell[(0, 0)] = val;
-        }
-        let mut z_view_mut = self.z_mat.view_mut((0, 0), (n + m, 1));
-        z_view_mut += z_mat.view_mut((0, 0), (n + m, 1));
-
-        self.initialized = true;
+        Ok(z_mat)
     }
 
-    pub fn solve_dc_mna(&mut self) {
-        //TODO: return a result of the solution, encoding whether the solve was initialized or not
-        assert!(self.initialized);
-        assert!(self.is_linear());
-
-        // Dimensions must be N+MxN+M, where N is #nodes and M is #ind v sources
-        let n = self.num_nodes();
-        let m = self.num_aux_variables();
-
-        eprintln!("A:\n{:.1}", self.a_mat);
-        eprintln!("z:\n{:.1}", self.z_mat);
+    pub fn solve_dc_mna(&mut self) -> Result<(), SimError> {
+        if !self.initialized {
+            return Err(SimError::Uninitialized);
+        }
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        self.x_mat_valid = false;
 
         // Rely on LU factorization to solve these systems
-        let lu = self.a_mat.clone().full_piv_lu();
+        let lu = self.factor_a_mat()?;
         let result = lu
             .solve(&self.z_mat)
-            .expect("Could not solve LU Factorization");
-        let mut x_mat_view = self.x_mat.view_mut((0, 0), (n + m, 1));
-        x_mat_view += result;
+            .expect("the factorization was checked for singularity");
+        self.x_mat.copy_from(&result);
         self.x_mat_valid = true;
+        Ok(())
+    }
+
+    /// LU factorization of the assembled `a_mat`, for analyses that solve it against many z
+    /// vectors. Fails with a singularity report if the matrix is singular.
+    pub(crate) fn factor_a_mat(&self) -> Result<FullPivLU<f64, Dyn, Dyn>, SimError> {
+        if !self.initialized {
+            return Err(SimError::Uninitialized);
+        }
+        let lu = self.a_mat.clone().full_piv_lu();
+        if singularity::is_singular(&lu, &self.a_mat) {
+            return Err(SimError::SingularMatrix(
+                self.singularity_report(&self.a_mat),
+            ));
+        }
+        Ok(lu)
     }
 
     pub fn num_nodes(&self) -> usize {
-        let mut nodeset: HashSet<u64> = HashSet::<u64>::new();
+        self.node_ids().len()
+    }
+
+    /// The ids of every non-ground node referenced by a component, in ascending order
+    pub(crate) fn node_ids(&self) -> BTreeSet<u64> {
+        let mut nodeset: BTreeSet<u64> = BTreeSet::<u64>::new();
 
         for component in &self.component_list {
             match component {
@@ -216,11 +434,25 @@ impl Netlist {
                     nodeset.insert(depsrc.positive_node);
                     nodeset.insert(depsrc.negative_node);
                 }
+                Component::VCVoltageSource(depsrc) => {
+                    nodeset.insert(depsrc.positive_node);
+                    nodeset.insert(depsrc.negative_node);
+                    nodeset.insert(depsrc.source_sensing_node);
+                    nodeset.insert(depsrc.sink_sensing_node);
+                }
+                Component::Capacitor(cap) => {
+                    nodeset.insert(cap.a_node);
+                    nodeset.insert(cap.b_node);
+                }
+                Component::Inductor(ind) => {
+                    nodeset.insert(ind.a_node);
+                    nodeset.insert(ind.b_node);
+                }
             }
         }
         // MNA A matrix does not include ground node as a node!
         nodeset.remove(&0u64);
-        nodeset.len()
+        nodeset
     }
 
     pub fn num_aux_variables(&self) -> usize {
@@ -233,32 +465,69 @@ impl Netlist {
                 Component::CCVoltageSource(_) => {
                     num_aux_variables += 1;
                 }
+                Component::VCVoltageSource(_) => {
+                    num_aux_variables += 1;
+                }
+                Component::Inductor(_) => {
+                    num_aux_variables += 1;
+                }
                 Component::ICurrentSource(_)
                 | Component::Resistor(_)
                 | Component::VCCurrentSource(_)
-                | Component::CCCurrentSource(_) => {}
+                | Component::CCCurrentSource(_)
+                | Component::Capacitor(_) => {}
             }
         }
 
         num_aux_variables
     }
 
-    pub fn get_node_voltages(&self) -> Option<DMatrix<f64>> {
-        if self.x_mat_valid && self.num_nodes.is_some() {
-            Some(
-                self.x_mat
-                    .view(
-                        (0, 0),
-                        (
-                            self.num_nodes
-                                .expect("Checked that num_nodes is some, but is none"),
-                            1,
-                        ),
-                    )
-                    .into(),
-            )
-        } else {
-            None
+    pub fn get_node_voltages(&self) -> Result<DMatrix<f64>, SimError> {
+        match self.num_nodes {
+            Some(num_nodes) if self.x_mat_valid => {
+                Ok(self.x_mat.view((0, 0), (num_nodes, 1)).into())
+            }
+            _ => Err(SimError::NoSolution),
+        }
+    }
+
+    /// Returns the solved voltage of a node, looked up by the name or id it was created with
+    pub fn get_node_voltage(&self, name: &str) -> Result<f64, SimError> {
+        if !self.x_mat_valid {
+            return Err(SimError::NoSolution);
+        }
+        self.nodes
+            .row_of(name)
+            .map(|row| self.x_mat[(row, 0)])
+            .ok_or_else(|| SimError::InvalidNode(name.to_string()))
+    }
+
+    /// Returns the solved branch current of a voltage source, dependent voltage source or
+    /// inductor. Positive current flows into the positive (or a_node) terminal and through the
+    /// component.
+    pub fn get_branch_current(&self, component: impl Into<ComponentRef>) -> Result<f64, SimError> {
+        if !self.x_mat_valid {
+            return Err(SimError::NoSolution);
+        }
+        let component = component.into();
+        let source_num = self
+            .find_component(&component)
+            .and_then(|handle| self.component_list[handle.0].source_num())
+            .ok_or(SimError::InvalidSourceReference(component))?;
+        let n = self.num_nodes.ok_or(SimError::NoSolution)?;
+        Ok(self.x_mat[(n + source_num as usize - 1, 0)])
+    }
+
+    /// Snapshot of the row layout chosen by the last `initialize_dc_mna`
+    pub(crate) fn solution_index(&self) -> Result<SolutionIndex, SimError> {
+        match self.num_nodes {
+            Some(num_nodes) if self.initialized => Ok(SolutionIndex {
+                nodes: self.nodes.clone(),
+                num_nodes,
+                component_names: self.component_names.clone(),
+                source_nums: self.component_list.iter().map(|c| c.source_num()).collect(),
+            }),
+            _ => Err(SimError::Uninitialized),
         }
     }
 
@@ -279,9 +548,14 @@ impl Default for Netlist {
     fn default() -> Self {
         Self {
             component_list: vec![],
+            component_names: HashMap::new(),
+            initial_conditions: BTreeMap::new(),
+            nodesets: BTreeMap::new(),
+            nodes: NodeTable::new(),
             initialized: false,
             num_nodes: None,
             x_mat_valid: false,
+            temperature: DEFAULT_TEMPERATURE,
 
             a_mat: Box::new(nalgebra::dmatrix![]),
             x_mat: Box::new(nalgebra::dmatrix![]),
@@ -293,10 +567,14 @@ impl Default for Netlist {
 #[allow(unused_imports)]
 mod tests {
     use super::*;
+    use crate::components::cc_current_source;
+    use crate::components::cc_voltage_source;
     use crate::components::independent_current_source;
     use crate::components::independent_voltage_source;
     use crate::components::resistor;
+    use crate::components::vc_current_source;
     use crate::components::Component;
+    use crate::validation::Diagnostic;
     use assert_float_eq::*;
     use std::sync::Arc;
 
@@ -310,7 +588,7 @@ mod tests {
         let mut net = Netlist::new();
 
         let resistor = resistor::Resistor::new(1, 0, 1.0);
-        let voltage_source = independent_voltage_source::IVoltageSource::new(1, 1, 0, 12.0);
+        let voltage_source = independent_voltage_source::IVoltageSource::new(1, 0, 12.0);
         net.add_component(Component::Resistor(resistor));
         net.add_component(Component::IVoltageSource(voltage_source));
     }
@@ -320,7 +598,7 @@ mod tests {
         let mut net = Netlist::new();
 
         let resistor = resistor::Resistor::new(1, 0, 1.0);
-        let voltage_source = independent_voltage_source::IVoltageSource::new(1, 1, 0, 12.0);
+        let voltage_source = independent_voltage_source::IVoltageSource::new(1, 0, 12.0);
         net.add_component(Component::Resistor(resistor));
         net.add_component(Component::IVoltageSource(voltage_source));
     }
@@ -329,7 +607,7 @@ mod tests {
     fn init_matrix_dimensions() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 1.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
         let r1 = resistor::Resistor::new(1, 2, 5.0);
         let r2 = resistor::Resistor::new(0, 2, 10.0);
         let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
@@ -345,7 +623,7 @@ mod tests {
         assert!(net.z_mat.ncols() == 0);
         assert!(net.z_mat.nrows() == 0);
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
         // a_mat should now be n+m x n+m, i.e. (2 node + 1 indep vsource)
         assert!(net.a_mat.ncols() == 3);
@@ -359,7 +637,7 @@ mod tests {
         // Based on example from S3.1.5 of http://qucs.github.io/docs/technical/technical.pdf
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 1.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
         let r1 = resistor::Resistor::new(1, 2, 5.0);
         let r2 = resistor::Resistor::new(0, 2, 10.0);
         let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
@@ -369,7 +647,7 @@ mod tests {
         net.add_component(Component::Resistor(r2));
         net.add_component(Component::ICurrentSource(i1));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
         assert_float_relative_eq!(net.a_mat.view((0, 0), (1, 1))[(0, 0)], 0.2f64);
         assert_float_relative_eq!(net.a_mat.view((0, 1), (1, 1))[(0, 0)], -0.2f64);
@@ -387,7 +665,7 @@ mod tests {
         // Based on example from S3.1.5 of http://qucs.github.io/docs/technical/technical.pdf
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 1.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
         let r1 = resistor::Resistor::new(1, 2, 5.0);
         let r2 = resistor::Resistor::new(0, 2, 10.0);
         let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
@@ -397,9 +675,9 @@ mod tests {
         net.add_component(Component::Resistor(r2));
         net.add_component(Component::ICurrentSource(i1));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         assert_float_relative_eq!(net.x_mat.view((0, 0), (1, 1))[(0, 0)], 1.0f64);
         assert_float_relative_eq!(net.x_mat.view((1, 0), (1, 1))[(0, 0)], 4.0f64);
@@ -410,7 +688,7 @@ mod tests {
     fn get_node_voltages() {
         let mut net = Netlist::new();
 
-        let v1 = independent_voltage_source::IVoltageSource::new(1, 1, 0, 1.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
         let r1 = resistor::Resistor::new(1, 2, 5.0);
         let r2 = resistor::Resistor::new(0, 2, 10.0);
         let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
@@ -420,9 +698,9 @@ mod tests {
         net.add_component(Component::Resistor(r2));
         net.add_component(Component::ICurrentSource(i1));
 
-        net.initialize_dc_mna();
+        net.initialize_dc_mna().expect("netlist should initialize");
 
-        net.solve_dc_mna();
+        net.solve_dc_mna().expect("netlist should solve");
 
         let node_voltages = net
             .get_node_voltages()
@@ -430,4 +708,204 @@ mod tests {
         assert_float_relative_eq!(node_voltages.view((0, 0), (1, 1))[(0, 0)], 1.0f64);
         assert_float_relative_eq!(node_voltages.view((1, 0), (1, 1))[(0, 0)], 4.0f64);
     }
+
+    #[test]
+    fn automatic_source_numbering() {
+        let mut net = Netlist::new();
+
+        let r1 = resistor::Resistor::new(1, 2, 1.0);
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let v2 = independent_voltage_source::IVoltageSource::new(2, 0, 3.0);
+
+        let r1 = net.add_component(Component::Resistor(r1));
+        let v1 = net.add_component(Component::IVoltageSource(v1));
+        let v2 = net
+            .add_named_component("V2", Component::IVoltageSource(v2))
+            .expect("V2 is a new name");
+
+        assert_eq!(net.component(r1).unwrap().source_num(), None);
+        assert_eq!(net.component(v1).unwrap().source_num(), Some(1));
+        assert_eq!(net.component(v2).unwrap().source_num(), Some(2));
+        assert_eq!(net.find_component(&"V2".into()), Some(v2));
+        assert_eq!(net.find_component(&ComponentHandle(3).into()), None);
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+
+        // Branch currents follow the node voltages in the order the sources were added
+        assert_float_relative_eq!(net.x_mat[(2, 0)], 2.0f64);
+        assert_float_relative_eq!(net.x_mat[(3, 0)], -2.0f64);
+    }
+
+    #[test]
+    fn branch_currents() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 2, 5.0);
+        let r2 = resistor::Resistor::new(0, 2, 10.0);
+        let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
+
+        let v1 = net
+            .add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        let r1 = net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::ICurrentSource(i1));
+
+        assert_eq!(net.get_branch_current(v1), Err(SimError::NoSolution));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_branch_current(v1).unwrap(), 0.6f64);
+        assert_float_relative_eq!(net.get_branch_current("V1").unwrap(), 0.6f64);
+        assert_eq!(
+            net.get_branch_current(r1),
+            Err(SimError::InvalidSourceReference(r1.into()))
+        );
+    }
+
+    #[test]
+    fn sparse_node_ids() {
+        // Same circuit as dc_mna_solve, with nodes 1 and 2 renumbered to 5 and 100
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(5, 0, 1.0);
+        let r1 = resistor::Resistor::new(5, 100, 5.0);
+        let r2 = resistor::Resistor::new(0, 100, 10.0);
+        let i1 = independent_current_source::ICurrentSource::new(0, 100, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::ICurrentSource(i1));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        assert!(net.a_mat.nrows() == 3);
+
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_node_voltage("5").unwrap(), 1.0f64);
+        assert_float_relative_eq!(net.get_node_voltage("100").unwrap(), 4.0f64);
+        assert_eq!(
+            net.get_node_voltage("1"),
+            Err(SimError::InvalidNode("1".to_string()))
+        );
+    }
+
+    #[test]
+    fn named_nodes() {
+        let mut net = Netlist::new();
+
+        let vin = net.node("vin");
+        let vout = net.node("vout");
+        let gnd = net.node("gnd");
+        let v1 = independent_voltage_source::IVoltageSource::new(vin, gnd, 1.0);
+        let r1 = resistor::Resistor::new(vin, vout, 5.0);
+        let r2 = resistor::Resistor::new(gnd, vout, 10.0);
+        let i1 = independent_current_source::ICurrentSource::new(gnd, vout, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::ICurrentSource(i1));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+
+        assert_float_relative_eq!(net.get_node_voltage("vin").unwrap(), 1.0f64);
+        assert_float_relative_eq!(net.get_node_voltage("vout").unwrap(), 4.0f64);
+        assert_eq!(
+            net.get_node_voltage("missing"),
+            Err(SimError::InvalidNode("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn solve_errors() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 5.0);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+
+        assert_eq!(net.solve_dc_mna(), Err(SimError::Uninitialized));
+        assert_eq!(net.get_node_voltages(), Err(SimError::NoSolution));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert!(net.get_node_voltages().is_ok());
+
+        // Adding a component invalidates the previous solution
+        let r2 = resistor::Resistor::new(1, 0, 5.0);
+        net.add_component(Component::Resistor(r2));
+        assert_eq!(net.get_node_voltage("1"), Err(SimError::NoSolution));
+        assert_eq!(net.solve_dc_mna(), Err(SimError::Uninitialized));
+    }
+
+    #[test]
+    fn singular_matrix() {
+        // The VCCS exactly cancels the resistor's conductance, leaving node 1 undetermined
+        let mut net = Netlist::new();
+
+        let r1 = resistor::Resistor::new(1, 0, 2.0);
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 1.0);
+        let g1 = vc_current_source::VCCurrentSource::new(1, 0, 0, 1, 0.5);
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::ICurrentSource(i1));
+        net.add_component(Component::VCCurrentSource(g1));
+
+        net.initialize_dc_mna().expect("netlist should initialize");
+        assert!(matches!(
+            net.solve_dc_mna(),
+            Err(SimError::SingularMatrix(report)) if report.floating_nodes == vec!["1"]
+        ));
+        assert_eq!(net.get_node_voltages(), Err(SimError::NoSolution));
+    }
+
+    #[test]
+    fn invalid_source_references() {
+        let mut net = Netlist::new();
+
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let r1 = net
+            .add_named_component("R1", Component::Resistor(r1))
+            .expect("R1 is a new name");
+        let r2 = resistor::Resistor::new(1, 0, 1.0);
+        assert_eq!(
+            net.add_named_component("R1", Component::Resistor(r2)),
+            Err(SimError::DuplicateComponentName("R1".to_string()))
+        );
+
+        // A resistor carries no branch current
+        let f1 = cc_current_source::CCCurrentSource::new(r1, 1, 0, 1.0);
+        net.add_component(Component::CCCurrentSource(f1));
+        assert_eq!(
+            net.initialize_dc_mna(),
+            Err(SimError::Topology(vec![
+                Diagnostic::InvalidControllingSource {
+                    component: "#1".to_string(),
+                    control: r1.into(),
+                }
+            ]))
+        );
+
+        let mut net = Netlist::new();
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let h1 = cc_voltage_source::CCVoltageSource::new("Vmissing", 1, 0, 1.0);
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::CCVoltageSource(h1));
+        assert_eq!(
+            net.initialize_dc_mna(),
+            Err(SimError::Topology(vec![
+                Diagnostic::InvalidControllingSource {
+                    component: "#1".to_string(),
+                    control: "Vmissing".into(),
+                }
+            ]))
+        );
+        assert_eq!(net.solve_dc_mna(), Err(SimError::Uninitialized));
+    }
 }
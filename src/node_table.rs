@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Ids handed out for string-named nodes start here, well above any id a caller is likely to pick
+/// by hand, so that named and numbered nodes can be mixed in one netlist without colliding.
+const NAMED_NODE_BASE: u64 = 1 << 32;
+
+/// Maps user-facing node names and ids onto the dense rows of the MNA system.
+///
+/// Components refer to nodes by `u64` id, with 0 as ground. Ids need not be contiguous: the rows
+/// are assigned in ascending id order when the MNA system is built, so a netlist using nodes 1, 5
+/// and 100 occupies rows 0, 1 and 2.
+#[derive(Default, Debug, Clone)]
+pub struct NodeTable {
+    names: HashMap<String, u64>,
+    rows: BTreeMap<u64, usize>,
+}
+
+#[allow(dead_code)]
+impl NodeTable {
+    pub fn new() -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    pub fn is_ground(name: &str) -> bool {
+        name == "0" || name.eq_ignore_ascii_case("gnd")
+    }
+
+    /// Returns the id of the node called `name`, allocating one if the name is new. "0" and
+    /// "gnd" are ground, and names that are plain integers refer to the node with that id.
+    pub fn node(&mut self, name: &str) -> u64 {
+        if let Some(id) = self.lookup(name) {
+            return id;
+        }
+        let id = NAMED_NODE_BASE + self.names.len() as u64;
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    /// Like `node`, but never allocates
+    pub fn lookup(&self, name: &str) -> Option<u64> {
+        if Self::is_ground(name) {
+            return Some(0);
+        }
+        if let Ok(id) = name.parse::<u64>() {
+            return Some(id);
+        }
+        self.names.get(name).copied()
+    }
+
+    /// The name a node was created with, or its id for numbered nodes
+    pub fn name_of(&self, id: u64) -> String {
+        self.names
+            .iter()
+            .find(|(_, &named_id)| named_id == id)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Assigns contiguous rows to `ids` in ascending order. Ground must not be included.
+    pub fn assign_rows(&mut self, ids: &BTreeSet<u64>) {
+        self.rows = ids.iter().enumerate().map(|(row, &id)| (id, row)).collect();
+    }
+
+    /// The MNA row of node `id`, if `assign_rows` has placed it
+    pub fn row(&self, id: u64) -> Option<usize> {
+        self.rows.get(&id).copied()
+    }
+
+    /// The MNA row of the node called `name`
+    pub fn row_of(&self, name: &str) -> Option<usize> {
+        self.lookup(name).and_then(|id| self.row(id))
+    }
+
+    /// The id of the node placed at `row`
+    pub fn id_at_row(&self, row: usize) -> Option<u64> {
+        self.rows
+            .iter()
+            .find(|(_, &assigned)| assigned == row)
+            .map(|(&id, _)| id)
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ground_aliases() {
+        let mut table = NodeTable::new();
+        assert_eq!(table.node("0"), 0);
+        assert_eq!(table.node("gnd"), 0);
+        assert_eq!(table.node("GND"), 0);
+    }
+
+    #[test]
+    fn names_and_ids() {
+        let mut table = NodeTable::new();
+        let vout = table.node("vout");
+        let bias = table.node("bias_n");
+        assert_ne!(vout, bias);
+        assert_eq!(table.node("vout"), vout);
+        assert_eq!(table.node("7"), 7);
+        assert_eq!(table.lookup("missing"), None);
+        assert_eq!(table.name_of(bias), "bias_n");
+        assert_eq!(table.name_of(7), "7");
+    }
+
+    #[test]
+    fn dense_rows() {
+        let mut table = NodeTable::new();
+        let vout = table.node("vout");
+        table.assign_rows(&BTreeSet::from([100, 1, vout, 5]));
+        assert_eq!(table.num_rows(), 4);
+        assert_eq!(table.row(1), Some(0));
+        assert_eq!(table.row(5), Some(1));
+        assert_eq!(table.row(100), Some(2));
+        assert_eq!(table.row_of("vout"), Some(3));
+        assert_eq!(table.row(0), None);
+        assert_eq!(table.id_at_row(2), Some(100));
+        assert_eq!(table.id_at_row(4), None);
+    }
+}
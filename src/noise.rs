@@ -0,0 +1,221 @@
+use crate::components::{Component, ComponentHandle, ComponentRef};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::sensitivity::solve_transpose;
+use crate::solution::{Output, SolutionIndex};
+
+/// Boltzmann's constant, in J/K
+const BOLTZMANN: f64 = 1.380649e-23;
+const ZERO_CELSIUS: f64 = 273.15;
+
+/// The noise one resistor contributes at the output
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseContribution {
+    pub component: ComponentHandle,
+    pub name: String,
+    /// Output noise power spectral density, in V^2/Hz (A^2/Hz for a current output)
+    pub density: f64,
+}
+
+/// Thermal noise at an output, referred back to an input source. Densities are power spectral
+/// densities, which add between uncorrelated sources; take the square root for V/sqrt(Hz).
+#[derive(Debug, Clone)]
+pub struct NoiseResult {
+    index: SolutionIndex,
+    temperature: f64,
+    gain: f64,
+    contributions: Vec<NoiseContribution>,
+}
+
+#[allow(dead_code)]
+impl NoiseResult {
+    /// The temperature the noise was computed at, in degrees Celsius
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// Total output noise density
+    pub fn output_density(&self) -> f64 {
+        self.contributions.iter().map(|c| c.density).sum()
+    }
+
+    /// Every resistor's share of the output noise, in netlist order
+    pub fn contributions(&self) -> &[NoiseContribution] {
+        &self.contributions
+    }
+
+    /// The output noise density due to one resistor
+    pub fn contribution(&self, component: impl Into<ComponentRef>) -> Option<f64> {
+        let handle = self.index.handle(&component.into())?;
+        self.contributions
+            .iter()
+            .find(|c| c.component == handle)
+            .map(|c| c.density)
+    }
+
+    /// The small-signal gain from the input source to the output
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    /// The output noise divided by the power gain from the input source: the noise the source
+    /// would need to carry to produce the same output in a noiseless circuit. Infinite if the
+    /// source does not reach the output.
+    pub fn input_referred_density(&self) -> f64 {
+        if self.gain == 0.0 {
+            f64::INFINITY
+        } else {
+            self.output_density() / (self.gain * self.gain)
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Thermal noise of every resistor at the netlist temperature. Each resistor is a 4kT/R
+    /// current noise source in parallel with it, and the adjoint solution A^T λ = c gives the
+    /// transfer from a current injected across any pair of nodes to the output, so one extra
+    /// solve covers them all. Dependent sources are noiseless, and capacitors and inductors are
+    /// taken at DC, where they are open and short circuits.
+    pub fn noise(
+        &mut self,
+        output: &Output,
+        source: impl Into<ComponentRef>,
+    ) -> Result<NoiseResult, SimError> {
+        let source = source.into();
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let (direction, _) = self.source_direction(&index, &source)?;
+        let selector = output.selector(&index, direction.nrows())?;
+        let lu = self.factor_a_mat()?;
+        let adjoint = solve_transpose(&lu, &selector);
+
+        let kt = BOLTZMANN * (self.temperature() + ZERO_CELSIUS);
+        let mut contributions = Vec::new();
+        for (idx, component) in self.components().iter().enumerate() {
+            let Component::Resistor(res) = component else {
+                continue;
+            };
+            let transfer =
+                index.voltage(&adjoint, res.a_node) - index.voltage(&adjoint, res.b_node);
+            let handle = ComponentHandle(idx);
+            contributions.push(NoiseContribution {
+                component: handle,
+                name: self.component_label(handle),
+                density: 4.0 * kt / res.resistance * transfer * transfer,
+            });
+        }
+
+        Ok(NoiseResult {
+            index,
+            temperature: self.temperature(),
+            gain: adjoint.dot(&direction),
+            contributions,
+        })
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn divider_noise() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 3e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("R2", Component::Resistor(r2))
+            .expect("new name");
+
+        // The output sees R1 || R2 = 750 ohms, whose noise is 4kT * 750
+        let output = Output::Voltage("2".to_string());
+        let result = net.noise(&output, "V1").expect("noise should solve");
+        let kt = BOLTZMANN * 300.15;
+        assert_eq!(result.temperature(), 27.0);
+        assert_float_relative_eq!(result.output_density(), 4.0 * kt * 750.0);
+        // Each resistor's share is set by the divider it forms with the other
+        assert_float_relative_eq!(
+            result.contribution("R1").unwrap(),
+            4.0 * kt / 1e3 * 750.0f64.powi(2)
+        );
+        assert_float_relative_eq!(
+            result.contribution("R2").unwrap(),
+            4.0 * kt / 3e3 * 750.0f64.powi(2)
+        );
+        assert_eq!(result.contributions()[0].name, "R1");
+        assert_eq!(result.contribution("V1"), None);
+        assert_float_relative_eq!(result.gain(), 0.75);
+        assert_float_relative_eq!(
+            result.input_referred_density(),
+            4.0 * kt * 750.0 / 0.75f64.powi(2)
+        );
+
+        // Noise power scales with absolute temperature
+        net.set_temperature(-273.15 + 600.3);
+        let hot = net.noise(&output, "V1").expect("noise should solve");
+        assert_float_relative_eq!(hot.output_density(), 2.0 * result.output_density());
+    }
+
+    #[test]
+    fn dependent_sources_are_noiseless() {
+        // A transconductance stage: G1 drives 10 mS * V(1) into the 1k load R2
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 0.0);
+        let r1 = resistor::Resistor::new(1, 0, 100.0);
+        let g1 = vc_current_source::VCCurrentSource::new(1, 0, 2, 0, 10e-3);
+        let r2 = resistor::Resistor::new(2, 0, 1e3);
+        let i1 = net.add_component(Component::ICurrentSource(i1));
+        let r1 = net.add_component(Component::Resistor(r1));
+        net.add_component(Component::VCCurrentSource(g1));
+        let r2 = net.add_component(Component::Resistor(r2));
+
+        let result = net
+            .noise(&Output::Voltage("2".to_string()), i1)
+            .expect("noise should solve");
+        assert_eq!(result.contributions().len(), 2);
+        let kt = BOLTZMANN * 300.15;
+        let gain = result.gain().abs();
+        assert_float_relative_eq!(gain, 100.0 * 10e-3 * 1e3);
+        assert_float_relative_eq!(
+            result.contribution(r1).unwrap(),
+            4.0 * kt * 100.0 * 10.0f64.powi(2)
+        );
+        assert_float_relative_eq!(result.contribution(r2).unwrap(), 4.0 * kt * 1e3);
+        // Referred to the input current: R1's own 4kT/R1 plus R2's divided by the gain
+        assert_float_relative_eq!(
+            result.input_referred_density(),
+            4.0 * kt / 100.0 + 4.0 * kt * 1e3 / 1e6
+        );
+    }
+
+    #[test]
+    fn unreachable_output() {
+        // V2 holds the output, so neither the input nor R2's noise reaches it
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 1e-3);
+        let r1 = resistor::Resistor::new(1, 0, 1e3);
+        let v2 = independent_voltage_source::IVoltageSource::new(2, 0, 1.0);
+        let r2 = resistor::Resistor::new(2, 0, 1e3);
+        let i1 = net.add_component(Component::ICurrentSource(i1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::IVoltageSource(v2));
+        net.add_component(Component::Resistor(r2));
+
+        let result = net
+            .noise(&Output::Voltage("2".to_string()), i1)
+            .expect("noise should solve");
+        assert_eq!(result.gain(), 0.0);
+        assert_eq!(result.output_density(), 0.0);
+        assert_eq!(result.input_referred_density(), f64::INFINITY);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::components::{Component, ComponentRef};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+
+/// A numeric property of one component that an analysis can vary
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parameter {
+    Resistance(ComponentRef),
+    Capacitance(ComponentRef),
+    Inductance(ComponentRef),
+    /// The operating-point value of an independent voltage or current source, its waveform at
+    /// t = 0. Setting it shifts the whole waveform, keeping its time function.
+    SourceValue(ComponentRef),
+    /// A factor on the whole waveform of an independent source, offset and amplitude alike.
+    /// It reads as 1 and scales the present waveform when set, so it is meant to be applied
+    /// through `with_parameter`. Source tolerances vary it.
+    SourceScale(ComponentRef),
+    /// The gain of a dependent source, in the component's own sign convention
+    Gain(ComponentRef),
+}
+
+#[allow(dead_code)]
+impl Parameter {
+    pub fn component(&self) -> &ComponentRef {
+        match self {
+            Parameter::Resistance(component)
+            | Parameter::Capacitance(component)
+            | Parameter::Inductance(component)
+            | Parameter::SourceValue(component)
+            | Parameter::SourceScale(component)
+            | Parameter::Gain(component) => component,
+        }
+    }
+
+    /// Reads the parameter from `component`, if it has one of this kind
+    fn get(&self, component: &Component) -> Option<f64> {
+        match (self, component) {
+            (Parameter::Resistance(_), Component::Resistor(res)) => Some(res.resistance),
+            (Parameter::Capacitance(_), Component::Capacitor(cap)) => Some(cap.capacitance),
+            (Parameter::Inductance(_), Component::Inductor(ind)) => Some(ind.inductance),
+            (Parameter::SourceValue(_), Component::IVoltageSource(vs)) => Some(vs.voltage_at(0.0)),
+            (Parameter::SourceValue(_), Component::ICurrentSource(is)) => Some(is.current_at(0.0)),
+            (Parameter::SourceScale(_), Component::IVoltageSource(_))
+            | (Parameter::SourceScale(_), Component::ICurrentSource(_)) => Some(1.0),
+            (Parameter::Gain(_), Component::VCCurrentSource(depsrc)) => Some(depsrc.gain()),
+            (Parameter::Gain(_), Component::VCVoltageSource(depsrc)) => Some(depsrc.gain()),
+            (Parameter::Gain(_), Component::CCCurrentSource(depsrc)) => Some(depsrc.gain()),
+            (Parameter::Gain(_), Component::CCVoltageSource(depsrc)) => Some(depsrc.gain()),
+            _ => None,
+        }
+    }
+
+    /// Writes the parameter into `component`, returning false if it has none of this kind
+    pub(crate) fn set(&self, component: &mut Component, value: f64) -> bool {
+        match (self, component) {
+            (Parameter::Resistance(_), Component::Resistor(res)) => res.resistance = value,
+            (Parameter::Capacitance(_), Component::Capacitor(cap)) => cap.capacitance = value,
+            (Parameter::Inductance(_), Component::Inductor(ind)) => ind.inductance = value,
+            (Parameter::SourceValue(_), Component::IVoltageSource(vs)) => {
+                vs.set_waveform(vs.waveform().shifted(value - vs.voltage_at(0.0)))
+            }
+            (Parameter::SourceValue(_), Component::ICurrentSource(is)) => {
+                is.set_waveform(is.waveform().shifted(value - is.current_at(0.0)))
+            }
+            (Parameter::SourceScale(_), Component::IVoltageSource(vs)) => {
+                vs.set_waveform(vs.waveform().scaled(value))
+            }
+            (Parameter::SourceScale(_), Component::ICurrentSource(is)) => {
+                is.set_waveform(is.waveform().scaled(value))
+            }
+            (Parameter::Gain(_), Component::VCCurrentSource(depsrc)) => depsrc.set_gain(value),
+            (Parameter::Gain(_), Component::VCVoltageSource(depsrc)) => depsrc.set_gain(value),
+            (Parameter::Gain(_), Component::CCCurrentSource(depsrc)) => depsrc.set_gain(value),
+            (Parameter::Gain(_), Component::CCVoltageSource(depsrc)) => depsrc.set_gain(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// The current value of `parameter`
+    pub fn parameter(&self, parameter: &Parameter) -> Result<f64, SimError> {
+        self.find_component(parameter.component())
+            .and_then(|handle| self.component(handle))
+            .and_then(|component| parameter.get(component))
+            .ok_or_else(|| SimError::InvalidParameter(parameter.clone()))
+    }
+
+    /// Changes `parameter` to `value`. The MNA system must be initialized again afterwards,
+    /// which every analysis does.
+    pub fn set_parameter(&mut self, parameter: &Parameter, value: f64) -> Result<(), SimError> {
+        let handle = self
+            .find_component(parameter.component())
+            .ok_or_else(|| SimError::InvalidParameter(parameter.clone()))?;
+        let component = self
+            .component_mut(handle)
+            .expect("find_component only returns handles in the netlist");
+        if parameter.set(component, value) {
+            Ok(())
+        } else {
+            Err(SimError::InvalidParameter(parameter.clone()))
+        }
+    }
+
+    /// Runs `analysis` with `parameter` temporarily set to `value`, restoring the component
+    /// exactly as it was afterwards, including any source waveform
+    pub fn with_parameter<T>(
+        &mut self,
+        parameter: &Parameter,
+        value: f64,
+        analysis: impl FnOnce(&mut Netlist) -> Result<T, SimError>,
+    ) -> Result<T, SimError> {
+        self.with_parameters(&[(parameter.clone(), value)], analysis)
+    }
+
+    /// Like `with_parameter`, for several parameters at once
+    pub fn with_parameters<T>(
+        &mut self,
+        values: &[(Parameter, f64)],
+        analysis: impl FnOnce(&mut Netlist) -> Result<T, SimError>,
+    ) -> Result<T, SimError> {
+        let mut saved = Vec::with_capacity(values.len());
+        for (parameter, _) in values {
+            let handle = self
+                .find_component(parameter.component())
+                .ok_or_else(|| SimError::InvalidParameter(parameter.clone()))?;
+            let component = self
+                .component(handle)
+                .expect("find_component only returns handles in the netlist");
+            saved.push((handle, component.clone()));
+        }
+        let result = values
+            .iter()
+            .try_for_each(|(parameter, value)| self.set_parameter(parameter, *value))
+            .and_then(|_| analysis(self));
+        // Restore in reverse so a component set twice ends up with its original value
+        for (handle, component) in saved.into_iter().rev() {
+            *self
+                .component_mut(handle)
+                .expect("find_component only returns handles in the netlist") = component;
+        }
+        result
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::waveform::Waveform;
+    use assert_float_eq::*;
+
+    #[test]
+    fn get_and_set() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let r1 = resistor::Resistor::new(1, 2, 1.0);
+        let r2 = resistor::Resistor::new(2, 0, 1.0);
+        let g1 = vc_current_source::VCCurrentSource::new(2, 0, 0, 2, 0.5);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        let r1 = net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_named_component("G1", Component::VCCurrentSource(g1))
+            .expect("new name");
+
+        let resistance = Parameter::Resistance(r1.into());
+        assert_eq!(net.parameter(&resistance), Ok(1.0));
+        net.set_parameter(&resistance, 3.0)
+            .expect("R1 has a resistance");
+        assert_eq!(net.parameter(&resistance), Ok(3.0));
+
+        let gain = Parameter::Gain("G1".into());
+        net.set_parameter(&gain, 0.0).expect("G1 has a gain");
+        net.set_parameter(&Parameter::SourceValue("V1".into()), 8.0)
+            .expect("V1 has a value");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 2.0);
+
+        let wrong_kind = Parameter::Capacitance(r1.into());
+        assert_eq!(
+            net.set_parameter(&wrong_kind, 1.0),
+            Err(SimError::InvalidParameter(wrong_kind.clone()))
+        );
+        assert!(net.parameter(&Parameter::Gain("G2".into())).is_err());
+    }
+
+    #[test]
+    fn temporary_values() {
+        let mut net = Netlist::new();
+        let sin = Waveform::Sin {
+            offset: 1.0,
+            amplitude: 1.0,
+            frequency: 1e3,
+            delay: 0.0,
+            damping: 0.0,
+            phase: 0.0,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, sin.clone());
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let v1 = net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+
+        let value = Parameter::SourceValue(v1.into());
+        let voltage = net
+            .with_parameter(&value, 5.0, |net| {
+                net.initialize_dc_mna()?;
+                net.solve_dc_mna()?;
+                net.get_node_voltage("1")
+            })
+            .expect("analysis should run");
+        assert_float_relative_eq!(voltage, 5.0);
+        // The waveform is back in place
+        match net.component(v1) {
+            Some(Component::IVoltageSource(vs)) => assert_eq!(vs.waveform(), &sin),
+            other => panic!("unexpected component {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,1013 @@
+use crate::components::capacitor::Capacitor;
+use crate::components::cc_current_source::CCCurrentSource;
+use crate::components::cc_voltage_source::CCVoltageSource;
+use crate::components::independent_current_source::ICurrentSource;
+use crate::components::independent_voltage_source::IVoltageSource;
+use crate::components::inductor::Inductor;
+use crate::components::resistor::Resistor;
+use crate::components::vc_current_source::VCCurrentSource;
+use crate::components::vc_voltage_source::VCVoltageSource;
+use crate::components::Component;
+use crate::netlist::Netlist;
+use crate::waveform::{pwl_times_ascend, Waveform};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A malformed SPICE deck. Lines and columns are 1-based and point at the offending token, or just
+/// past the end of the card when a required field is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+}
+
+/// One logical card, i.e. a physical line plus any `+` continuation lines that follow it
+#[derive(Debug)]
+struct Card {
+    tokens: Vec<Token>,
+    // Position just past the last character of the card, used when a field is missing
+    end_line: usize,
+    end_column: usize,
+}
+
+impl Card {
+    fn name(&self) -> &Token {
+        &self.tokens[0]
+    }
+
+    fn field(&self, index: usize, what: &str) -> Result<&Token, ParseError> {
+        self.tokens.get(index).ok_or_else(|| {
+            ParseError::new(
+                self.end_line,
+                self.end_column,
+                format!("{} is missing {}", self.name().text, what),
+            )
+        })
+    }
+
+    fn expect_len(&self, len: usize) -> Result<(), ParseError> {
+        match self.tokens.get(len) {
+            Some(extra) => Err(extra.error(format!("unexpected token '{}'", extra.text))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a SPICE deck into a populated `Netlist`.
+///
+/// As in SPICE, the first line is the title and is ignored. Nodes may be numbered or named, with
+/// "0" and "gnd" as ground. Supported element cards are R, C, L, V, I, E, G, F and H, with V and I
+/// taking a DC value or a PULSE, SIN, PWL, EXP or SFFM function and an optional AC value. `*`
+/// starts a comment line, `;` and `$` start inline comments, a leading `+` continues the previous
+/// card, and `.end` terminates the deck. `.ic` and `.nodeset` cards list `V(node)=value` pairs
+/// that set initial conditions and operating-point hints. Elements are added to the netlist under
+/// their upper-cased names.
+#[allow(dead_code)]
+pub fn parse_netlist(source: &str) -> Result<Netlist, ParseError> {
+    let (control_cards, cards): (Vec<Card>, Vec<Card>) = split_cards(source)?
+        .into_iter()
+        .partition(|card| card.name().text.starts_with('.'));
+
+    // F and H cards name their controlling source, which may appear later in the deck, so all
+    // element names are collected before any component is built.
+    let mut element_names: HashMap<String, usize> = HashMap::new();
+    for card in &cards {
+        let name = card.name();
+        let key = name.text.to_ascii_uppercase();
+        if let Some(first_line) = element_names.insert(key, name.line) {
+            return Err(name.error(format!(
+                "duplicate element name '{}' (first defined on line {})",
+                name.text, first_line
+            )));
+        }
+    }
+
+    let mut net = Netlist::new();
+    for card in &cards {
+        let component = parse_element(card, &element_names, &mut net)?;
+        net.add_named_component(&card.name().text.to_ascii_uppercase(), component)
+            .expect("element names were checked for duplicates above");
+    }
+    for card in &control_cards {
+        let is_nodeset = card.name().text.eq_ignore_ascii_case(".nodeset");
+        for (node, voltage) in parse_node_voltages(card, &mut net)? {
+            if is_nodeset {
+                net.set_nodeset(node, voltage);
+            } else {
+                net.set_initial_condition(node, voltage);
+            }
+        }
+    }
+    Ok(net)
+}
+
+/// Parses the `V(node)=value` list of a `.ic` or `.nodeset` card
+fn parse_node_voltages(card: &Card, net: &mut Netlist) -> Result<Vec<(u64, f64)>, ParseError> {
+    card.field(1, "a node voltage")?;
+    let mut voltages = vec![];
+    let mut idx = 1;
+    while let Some(token) = card.tokens.get(idx) {
+        if !token.text.eq_ignore_ascii_case("v") {
+            return Err(token.error(format!("expected V(node)=value, found '{}'", token.text)));
+        }
+        let node_token = card.field(idx + 1, "a node")?;
+        let node = parse_node(net, node_token)?;
+        if node == 0 {
+            return Err(node_token.error("ground cannot be given a voltage"));
+        }
+        voltages.push((node, parse_value(card.field(idx + 2, "a voltage")?)?));
+        idx += 3;
+    }
+    Ok(voltages)
+}
+
+fn split_cards(source: &str) -> Result<Vec<Card>, ParseError> {
+    let mut cards: Vec<Card> = vec![];
+
+    // Line 1 is always the title card
+    for (line_idx, raw_line) in source.lines().enumerate().skip(1) {
+        let line = line_idx + 1;
+        let content = strip_inline_comment(raw_line);
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('*') {
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix('+') {
+            let offset = content[..content.len() - rest.len()].chars().count();
+            let card = cards.last_mut().ok_or_else(|| {
+                ParseError::new(line, offset, "continuation line with no card to continue")
+            })?;
+            card.tokens.extend(tokenize(rest, line, offset));
+            card.end_line = line;
+            card.end_column = content.trim_end().chars().count() + 1;
+            continue;
+        }
+
+        let tokens = tokenize(content, line, 0);
+        if tokens.is_empty() {
+            continue;
+        }
+        let first = &tokens[0];
+        if first.text.starts_with('.') {
+            match first.text.to_ascii_lowercase().as_str() {
+                ".end" => break,
+                // Kept as cards so that continuation lines attach to them
+                ".ic" | ".nodeset" => {}
+                _ => {
+                    return Err(first.error(format!("unsupported control card '{}'", first.text)));
+                }
+            }
+        }
+
+        cards.push(Card {
+            end_line: line,
+            end_column: content.trim_end().chars().count() + 1,
+            tokens,
+        });
+    }
+
+    Ok(cards)
+}
+
+fn strip_inline_comment(line: &str) -> &str {
+    let mut prev_is_space = true;
+    for (idx, ch) in line.char_indices() {
+        if ch == ';' || (ch == '$' && prev_is_space) {
+            return &line[..idx];
+        }
+        prev_is_space = ch.is_whitespace();
+    }
+    line
+}
+
+/// Splits on whitespace, commas, parentheses and `=`, recording the 1-based column of each token.
+/// `offset` is the number of characters of the physical line that precede `text`.
+fn tokenize(text: &str, line: usize, offset: usize) -> Vec<Token> {
+    let is_separator = |c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '=');
+    let mut tokens = vec![];
+    let mut start: Option<usize> = None;
+    for (idx, ch) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (start, is_separator(ch)) {
+            (None, false) => start = Some(idx),
+            (Some(s), true) => {
+                tokens.push(Token {
+                    text: text[s..idx].to_string(),
+                    line,
+                    column: offset + text[..s].chars().count() + 1,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+fn parse_element(
+    card: &Card,
+    element_names: &HashMap<String, usize>,
+    net: &mut Netlist,
+) -> Result<Component, ParseError> {
+    let name = card.name();
+    let key = name.text.to_ascii_uppercase();
+    let kind = key.chars().next().expect("tokens are never empty");
+
+    match kind {
+        'R' => {
+            let a_node = parse_node(net, card.field(1, "its first node")?)?;
+            let b_node = parse_node(net, card.field(2, "its second node")?)?;
+            let value_token = card.field(3, "a resistance")?;
+            let resistance = parse_value(value_token)?;
+            if resistance == 0.0 {
+                return Err(value_token.error("resistance must be non-zero"));
+            }
+            card.expect_len(4)?;
+            Ok(Component::Resistor(Resistor::new(
+                a_node, b_node, resistance,
+            )))
+        }
+        'C' => {
+            let a_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let b_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let value_token = card.field(3, "a capacitance")?;
+            let capacitance = parse_value(value_token)?;
+            if capacitance <= 0.0 {
+                return Err(value_token.error("capacitance must be positive"));
+            }
+            let mut capacitor = Capacitor::new(a_node, b_node, capacitance);
+            let mut len = 4;
+            if let Some(token) = card.tokens.get(4) {
+                if !token.text.eq_ignore_ascii_case("ic") {
+                    return Err(token.error(format!("unexpected token '{}'", token.text)));
+                }
+                capacitor = capacitor
+                    .with_initial_voltage(parse_value(card.field(5, "an initial voltage")?)?);
+                len = 6;
+            }
+            card.expect_len(len)?;
+            Ok(Component::Capacitor(capacitor))
+        }
+        'L' => {
+            let a_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let b_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let value_token = card.field(3, "an inductance")?;
+            let inductance = parse_value(value_token)?;
+            if inductance <= 0.0 {
+                return Err(value_token.error("inductance must be positive"));
+            }
+            let mut inductor = Inductor::new(a_node, b_node, inductance);
+            let mut len = 4;
+            if let Some(token) = card.tokens.get(4) {
+                if !token.text.eq_ignore_ascii_case("ic") {
+                    return Err(token.error(format!("unexpected token '{}'", token.text)));
+                }
+                inductor = inductor
+                    .with_initial_current(parse_value(card.field(5, "an initial current")?)?);
+                len = 6;
+            }
+            card.expect_len(len)?;
+            Ok(Component::Inductor(inductor))
+        }
+        'V' => {
+            let positive_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let negative_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let (card, ac) = split_ac_spec(card, 3)?;
+            let voltage = parse_source_waveform(&card, 3)?;
+            let (magnitude, phase) = ac.unwrap_or_default();
+            Ok(Component::IVoltageSource(
+                IVoltageSource::from_waveform(positive_node, negative_node, voltage)
+                    .with_ac(magnitude, phase),
+            ))
+        }
+        'I' => {
+            let source_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let sink_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let (card, ac) = split_ac_spec(card, 3)?;
+            let current = parse_source_waveform(&card, 3)?;
+            let (magnitude, phase) = ac.unwrap_or_default();
+            Ok(Component::ICurrentSource(
+                ICurrentSource::from_waveform(source_node, sink_node, current)
+                    .with_ac(magnitude, phase),
+            ))
+        }
+        'G' => {
+            let source_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let sink_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let source_sensing_node = parse_node(net, card.field(3, "its positive control node")?)?;
+            let sink_sensing_node = parse_node(net, card.field(4, "its negative control node")?)?;
+            let gain = parse_value(card.field(5, "a transconductance")?)?;
+            card.expect_len(6)?;
+            Ok(Component::VCCurrentSource(VCCurrentSource::new(
+                source_sensing_node,
+                sink_sensing_node,
+                source_node,
+                sink_node,
+                gain,
+            )))
+        }
+        'E' => {
+            let positive_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let negative_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let source_sensing_node = parse_node(net, card.field(3, "its positive control node")?)?;
+            let sink_sensing_node = parse_node(net, card.field(4, "its negative control node")?)?;
+            let gain = parse_value(card.field(5, "a voltage gain")?)?;
+            card.expect_len(6)?;
+            Ok(Component::VCVoltageSource(VCVoltageSource::new(
+                source_sensing_node,
+                sink_sensing_node,
+                positive_node,
+                negative_node,
+                gain,
+            )))
+        }
+        'F' => {
+            let source_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let sink_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let control =
+                parse_controlling_source(card.field(3, "a controlling source")?, element_names)?;
+            let gain = parse_value(card.field(4, "a current gain")?)?;
+            card.expect_len(5)?;
+            Ok(Component::CCCurrentSource(CCCurrentSource::new(
+                control,
+                source_node,
+                sink_node,
+                gain,
+            )))
+        }
+        'H' => {
+            let positive_node = parse_node(net, card.field(1, "its positive node")?)?;
+            let negative_node = parse_node(net, card.field(2, "its negative node")?)?;
+            let control =
+                parse_controlling_source(card.field(3, "a controlling source")?, element_names)?;
+            let transresistance = parse_value(card.field(4, "a transresistance")?)?;
+            card.expect_len(5)?;
+            // CCVoltageSource enforces V(+) - V(-) = -gain * I(control), whereas SPICE defines
+            // V(+) - V(-) = r * I(control)
+            Ok(Component::CCVoltageSource(CCVoltageSource::new(
+                control,
+                positive_node,
+                negative_node,
+                -transresistance,
+            )))
+        }
+        _ => Err(name.error(format!("unknown element type '{}'", name.text))),
+    }
+}
+
+/// Removes an `AC magnitude [phase]` specification from the tail of an independent source card,
+/// returning the rest of the card and the magnitude and phase in degrees
+fn split_ac_spec(card: &Card, index: usize) -> Result<(Card, Option<(f64, f64)>), ParseError> {
+    let mut tokens = card.tokens.clone();
+    let Some(ac_pos) = (index..tokens.len()).find(|&i| tokens[i].text.eq_ignore_ascii_case("ac"))
+    else {
+        return Ok((
+            Card {
+                tokens,
+                end_line: card.end_line,
+                end_column: card.end_column,
+            },
+            None,
+        ));
+    };
+    let magnitude = parse_value(card.field(ac_pos + 1, "an AC magnitude")?)?;
+    let mut ac_end = ac_pos + 2;
+    let mut phase = 0.0;
+    if let Some(token) = card.tokens.get(ac_end) {
+        if !is_source_function(token) && !token.text.eq_ignore_ascii_case("dc") {
+            phase = parse_value(token)?;
+            ac_end += 1;
+        }
+    }
+    tokens.drain(ac_pos..ac_end);
+    Ok((
+        Card {
+            tokens,
+            end_line: card.end_line,
+            end_column: card.end_column,
+        },
+        Some((magnitude, phase)),
+    ))
+}
+
+/// Parses the `[DC] value` or time-dependent function tail of an independent source card. A
+/// missing value means 0. Functions take their arguments in SPICE order, e.g.
+/// `PULSE(v1 v2 td tr tf pw per)`, and `PWL FILE=name` reads the points from a file.
+fn parse_source_waveform(card: &Card, mut index: usize) -> Result<Waveform, ParseError> {
+    let mut dc_value = None;
+    if let Some(token) = card.tokens.get(index) {
+        if token.text.eq_ignore_ascii_case("dc") {
+            dc_value = Some(parse_value(card.field(index + 1, "a DC value")?)?);
+            index += 2;
+        } else if !is_source_function(token) {
+            dc_value = Some(parse_value(token)?);
+            index += 1;
+        }
+    }
+    let Some(token) = card.tokens.get(index) else {
+        return Ok(Waveform::Dc(dc_value.unwrap_or(0.0)));
+    };
+    if !is_source_function(token) {
+        return Err(token.error(format!("unexpected token '{}'", token.text)));
+    }
+    // A source has one waveform, and its value at t=0 is the operating point, so a separate DC
+    // value would be silently ignored
+    if dc_value.is_some() {
+        return Err(token.error(format!(
+            "{} cannot follow a DC value",
+            token.text.to_ascii_uppercase()
+        )));
+    }
+    let function = token.text.to_ascii_lowercase();
+
+    if function == "pwl" {
+        if let Some(file) = card.tokens.get(index + 1) {
+            if file.text.eq_ignore_ascii_case("file") {
+                let path = card.field(index + 2, "a PWL file name")?;
+                card.expect_len(index + 3)?;
+                let path_text = path.text.trim_matches('"');
+                return Waveform::pwl_from_file(path_text).map_err(|err| {
+                    path.error(format!("cannot read PWL file '{}': {}", path_text, err))
+                });
+            }
+        }
+    }
+
+    let args = card.tokens[index + 1..]
+        .iter()
+        .map(parse_value)
+        .collect::<Result<Vec<f64>, ParseError>>()?;
+    let arg = |idx: usize, default: f64| args.get(idx).copied().unwrap_or(default);
+    let (required, allowed) = match function.as_str() {
+        "pulse" => (2, 7),
+        "sin" => (3, 6),
+        "exp" => (2, 6),
+        "sffm" => (3, 5),
+        _ => (2, usize::MAX),
+    };
+    if args.len() < required {
+        return Err(ParseError::new(
+            card.end_line,
+            card.end_column,
+            format!(
+                "{} needs at least {} arguments, got {}",
+                token.text.to_ascii_uppercase(),
+                required,
+                args.len()
+            ),
+        ));
+    }
+    if args.len() > allowed {
+        let extra = &card.tokens[index + 1 + allowed];
+        return Err(extra.error(format!("unexpected token '{}'", extra.text)));
+    }
+
+    let waveform = match function.as_str() {
+        "pulse" => Waveform::Pulse {
+            initial: args[0],
+            pulsed: args[1],
+            delay: arg(2, 0.0),
+            rise: arg(3, 0.0),
+            fall: arg(4, 0.0),
+            width: arg(5, f64::INFINITY),
+            period: arg(6, f64::INFINITY),
+        },
+        "sin" => Waveform::Sin {
+            offset: args[0],
+            amplitude: args[1],
+            frequency: args[2],
+            delay: arg(3, 0.0),
+            damping: arg(4, 0.0),
+            phase: arg(5, 0.0),
+        },
+        // SPICE defaults the time constants to the transient step, which a source card does
+        // not know, so missing ones give a sharp step and a missing fall delay never falls
+        "exp" => {
+            if let Some(tau) = [3, 5].into_iter().find(|&idx| arg(idx, 0.0) < 0.0) {
+                let tau_token = &card.tokens[index + 1 + tau];
+                return Err(tau_token.error("EXP time constants must not be negative"));
+            }
+            Waveform::Exp {
+                initial: args[0],
+                pulsed: args[1],
+                rise_delay: arg(2, 0.0),
+                rise_tau: arg(3, 0.0),
+                fall_delay: arg(4, f64::INFINITY),
+                fall_tau: arg(5, 0.0),
+            }
+        }
+        // Without a modulation index the carrier is unmodulated
+        "sffm" => Waveform::Sffm {
+            offset: args[0],
+            amplitude: args[1],
+            carrier: args[2],
+            modulation_index: arg(3, 0.0),
+            signal: arg(4, 0.0),
+        },
+        _ => {
+            if args.len() % 2 != 0 {
+                let last = &card.tokens[card.tokens.len() - 1];
+                return Err(last.error("PWL needs time-value pairs"));
+            }
+            let points: Vec<(f64, f64)> = args.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+            if !pwl_times_ascend(&points) {
+                return Err(token.error("PWL times must be in ascending order"));
+            }
+            Waveform::Pwl(points)
+        }
+    };
+    // Only a PULSE period can be invalid, and only when given as the last argument
+    waveform
+        .validate()
+        .map_err(|message| card.tokens[card.tokens.len() - 1].error(message))?;
+    Ok(waveform)
+}
+
+fn is_source_function(token: &Token) -> bool {
+    matches!(
+        token.text.to_ascii_lowercase().as_str(),
+        "pulse" | "sin" | "pwl" | "exp" | "sffm"
+    )
+}
+
+/// Returns the upper-cased name of the voltage source a F or H card is controlled by
+fn parse_controlling_source(
+    token: &Token,
+    element_names: &HashMap<String, usize>,
+) -> Result<String, ParseError> {
+    let key = token.text.to_ascii_uppercase();
+    if key.starts_with('V') && element_names.contains_key(&key) {
+        Ok(key)
+    } else {
+        Err(token.error(format!(
+            "controlling source '{}' is not a voltage source in this deck",
+            token.text
+        )))
+    }
+}
+
+/// Node names are case-insensitive; "0" and "gnd" are ground
+fn parse_node(net: &mut Netlist, token: &Token) -> Result<u64, ParseError> {
+    let name = token.text.to_ascii_lowercase();
+    if name.starts_with('.') || name.starts_with('-') {
+        return Err(token.error(format!("invalid node name '{}'", token.text)));
+    }
+    Ok(net.node(&name))
+}
+
+/// Parses a SPICE number such as `10`, `-1.5e3` or `2.2k`. Scale factors (`f p n u m k meg g t
+/// mil`) are case-insensitive and any trailing unit letters (`10kOhm`, `5V`) are ignored.
+fn parse_value(token: &Token) -> Result<f64, ParseError> {
+    let text = token.text.to_ascii_lowercase();
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-') {
+        end += 1;
+    }
+    let mantissa_start = end;
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    if end == mantissa_start {
+        return Err(token.error(format!("invalid number '{}'", token.text)));
+    }
+    // An 'e' right after the mantissa starts an exponent, so "1e" and "1e+" are incomplete
+    if end < bytes.len() && bytes[end] == b'e' {
+        let mut exp_end = end + 1;
+        if exp_end < bytes.len() && (bytes[exp_end] == b'+' || bytes[exp_end] == b'-') {
+            exp_end += 1;
+        }
+        let digits_start = exp_end;
+        while exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+            exp_end += 1;
+        }
+        if exp_end == digits_start {
+            return Err(token.error(format!("invalid number '{}'", token.text)));
+        }
+        end = exp_end;
+    }
+
+    let number: f64 = text[..end]
+        .parse()
+        .map_err(|_| token.error(format!("invalid number '{}'", token.text)))?;
+
+    let suffix = &text[end..];
+    let scale = if suffix.starts_with("meg") {
+        1e6
+    } else if suffix.starts_with("mil") {
+        25.4e-6
+    } else {
+        match suffix.chars().next() {
+            None => 1.0,
+            Some('t') => 1e12,
+            Some('g') => 1e9,
+            Some('k') => 1e3,
+            Some('m') => 1e-3,
+            Some('u') => 1e-6,
+            Some('n') => 1e-9,
+            Some('p') => 1e-12,
+            Some('f') => 1e-15,
+            Some(c) if c.is_ascii_alphabetic() => 1.0,
+            Some(_) => return Err(token.error(format!("invalid number '{}'", token.text))),
+        }
+    };
+    if !suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(token.error(format!("invalid number '{}'", token.text)));
+    }
+
+    Ok(number * scale)
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use assert_float_eq::*;
+
+    #[allow(dead_code)]
+    fn token(text: &str) -> Token {
+        Token {
+            text: text.to_string(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    #[test]
+    fn values_with_scale_factors() {
+        assert_float_relative_eq!(parse_value(&token("10")).unwrap(), 10.0);
+        assert_float_relative_eq!(parse_value(&token("-1.5e3")).unwrap(), -1500.0);
+        assert_float_relative_eq!(parse_value(&token("2.2k")).unwrap(), 2200.0);
+        assert_float_relative_eq!(parse_value(&token("1MEG")).unwrap(), 1e6);
+        assert_float_relative_eq!(parse_value(&token("3m")).unwrap(), 3e-3);
+        assert_float_relative_eq!(parse_value(&token("10uF")).unwrap(), 10e-6);
+        assert_float_relative_eq!(parse_value(&token("5V")).unwrap(), 5.0);
+        assert_float_relative_eq!(parse_value(&token("1kOhm")).unwrap(), 1000.0);
+        assert!(parse_value(&token("abc")).is_err());
+        assert!(parse_value(&token("1.0.0")).is_err());
+        assert!(parse_value(&token("1k2")).is_err());
+        assert!(parse_value(&token("1e")).is_err());
+        assert!(parse_value(&token("1e+V")).is_err());
+    }
+
+    #[test]
+    fn comments_and_continuations() {
+        let deck = "title line\n\
+                    * a comment\n\
+                    V1 1 0 ; inline comment\n\
+                    + DC 12\n\
+                    R1 1 0 $ another comment\n\
+                    + 4\n\
+                    .end\n\
+                    R2 1 0 garbage\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("1").unwrap(), 12.0);
+        assert_float_relative_eq!(net.x_mat[(1, 0)], -3.0);
+    }
+
+    #[test]
+    fn all_element_types() {
+        // Same circuits as the component-level grounded tests
+        let deck = "vccs\n\
+                    V1 1 0 2\n\
+                    R1 1 2 1\n\
+                    R2 0 2 1\n\
+                    G1 3 0 2 0 -1\n\
+                    R3 3 0 2\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        let node_voltages = net.get_node_voltages().expect("solved");
+        assert_float_relative_eq!(node_voltages[(2, 0)], 2.0);
+
+        let deck = "vcvs\n\
+                    V1 1 0 2\n\
+                    R1 1 2 1\n\
+                    R2 0 2 1\n\
+                    E1 4 3 2 0 2\n\
+                    R3 3 0 1\n\
+                    R4 4 0 1\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("3").unwrap(), -1.0);
+        assert_float_relative_eq!(net.get_node_voltage("4").unwrap(), 1.0);
+
+        let deck = "cccs\n\
+                    F1 3 0 V2 2\n\
+                    V1 1 0 DC 2\n\
+                    V2 0 2 0\n\
+                    R1 1 2 2\n\
+                    R2 3 0 0.5\n\
+                    .END";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        let node_voltages = net.get_node_voltages().expect("solved");
+        assert_float_relative_eq!(node_voltages[(2, 0)], 1.0);
+
+        // SPICE H: V(3) = 0.5 * I(V2) where I(V2) = V(3) - 2 flows into its positive terminal
+        let deck = "ccvs\n\
+                    V1 1 0 2\n\
+                    V2 2 1 0\n\
+                    R1 2 3 1\n\
+                    R2 0 3 1\n\
+                    H1 3 0 V2 0.5\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        let node_voltages = net.get_node_voltages().expect("solved");
+        assert_float_relative_eq!(node_voltages[(2, 0)], -2.0);
+    }
+
+    #[test]
+    fn capacitors() {
+        let deck = "rc\n\
+                    V1 1 0 2\n\
+                    R1 1 2 1k\n\
+                    C1 2 0 1u IC=0.5\n\
+                    R2 2 0 1k\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 1.0);
+
+        let deck = "rl\n\
+                    V1 1 0 2\n\
+                    R1 1 2 1k\n\
+                    L1 2 0 1m IC=1m\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_branch_current("L1").unwrap(), 2e-3);
+
+        let err = parse_netlist("t\nC1 1 0 1u IX=2\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 11));
+        let err = parse_netlist("t\nC1 1 0 -1u\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+    }
+
+    #[allow(dead_code)]
+    fn source_waveform(net: &Netlist, name: &str) -> Waveform {
+        let handle = net.find_component(&name.into()).expect("source exists");
+        match net.component(handle) {
+            Some(Component::IVoltageSource(vs)) => vs.waveform().clone(),
+            Some(Component::ICurrentSource(is)) => is.waveform().clone(),
+            other => panic!("{} is not an independent source: {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn source_waveforms() {
+        let path = std::env::temp_dir().join(format!("ana_sim_deck_{}.pwl", std::process::id()));
+        std::fs::write(&path, "0 0\n1e-3 1\n").unwrap();
+        let deck = format!(
+            "sources\n\
+             V1 1 0 PULSE(0 5 1u 1n 1n 1u 2u)\n\
+             V2 2 0 SIN(0.5 1 1k)\n\
+             V3 3 0 PWL(0 0 1m 1 2m 1)\n\
+             I1 0 4 EXP(0 1m 1u 1u 5u 2u)\n\
+             I2 0 5 SFFM(0 1 1meg 2 1k)\n\
+             V4 6 0 PWL FILE=\"{}\"\n\
+             R1 1 0 1\nR2 2 0 1\nR3 3 0 1\nR4 4 0 1\nR5 5 0 1\nR6 6 0 1\n",
+            path.display()
+        );
+        let net = parse_netlist(&deck).expect("deck should parse");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            source_waveform(&net, "V1"),
+            Waveform::Pulse {
+                initial: 0.0,
+                pulsed: 5.0,
+                delay: 1e-6,
+                rise: 1e-9,
+                fall: 1e-9,
+                width: 1e-6,
+                period: 2e-6,
+            }
+        );
+        assert_eq!(
+            source_waveform(&net, "V2"),
+            Waveform::Sin {
+                offset: 0.5,
+                amplitude: 1.0,
+                frequency: 1e3,
+                delay: 0.0,
+                damping: 0.0,
+                phase: 0.0,
+            }
+        );
+        assert_eq!(
+            source_waveform(&net, "V3"),
+            Waveform::Pwl(vec![(0.0, 0.0), (1e-3, 1.0), (2e-3, 1.0)])
+        );
+        assert!(matches!(
+            source_waveform(&net, "I1"),
+            Waveform::Exp { fall_tau, .. } if fall_tau == 2e-6
+        ));
+        assert!(matches!(
+            source_waveform(&net, "I2"),
+            Waveform::Sffm { carrier, .. } if carrier == 1e6
+        ));
+
+        // EXP and SFFM fill in their trailing arguments
+        let deck = "defaults\n\
+                    V1 1 0 EXP(0 1)\n\
+                    V2 2 0 SFFM(0.5 1 1k)\n\
+                    R1 1 0 1\nR2 2 0 1\n";
+        let defaults = parse_netlist(deck).expect("deck should parse");
+        assert_eq!(
+            source_waveform(&defaults, "V1"),
+            Waveform::Exp {
+                initial: 0.0,
+                pulsed: 1.0,
+                rise_delay: 0.0,
+                rise_tau: 0.0,
+                fall_delay: f64::INFINITY,
+                fall_tau: 0.0,
+            }
+        );
+        assert_eq!(
+            source_waveform(&defaults, "V2"),
+            Waveform::Sffm {
+                offset: 0.5,
+                amplitude: 1.0,
+                carrier: 1e3,
+                modulation_index: 0.0,
+                signal: 0.0,
+            }
+        );
+        let err = parse_netlist("t\nV1 1 0 SFFM(0 1)\n").unwrap_err();
+        assert_eq!(err.message, "SFFM needs at least 3 arguments, got 2");
+        assert_eq!(
+            source_waveform(&net, "V4"),
+            Waveform::Pwl(vec![(0.0, 0.0), (1e-3, 1.0)])
+        );
+
+        let deck = "ac\n\
+                    V1 1 0 DC 1 AC 2 -90\n\
+                    V2 2 0 AC 0.5 SIN(0 1 1k)\n\
+                    I1 0 3 AC 1m\n\
+                    R1 1 0 1\nR2 2 0 1\nR3 3 0 1\n";
+        let net = parse_netlist(deck).expect("deck should parse");
+        assert_eq!(source_waveform(&net, "V1"), Waveform::Dc(1.0));
+        assert!(matches!(source_waveform(&net, "V2"), Waveform::Sin { .. }));
+        assert_eq!(source_waveform(&net, "I1"), Waveform::Dc(0.0));
+        let phasor = |name: &str| match net.component(net.find_component(&name.into()).unwrap()) {
+            Some(Component::IVoltageSource(vs)) => vs.ac_phasor(),
+            Some(Component::ICurrentSource(is)) => is.ac_phasor(),
+            _ => unreachable!(),
+        };
+        assert_float_absolute_eq!(phasor("V1").im, -2.0, 1e-12);
+        assert_float_absolute_eq!(phasor("V1").re, 0.0, 1e-12);
+        assert_float_relative_eq!(phasor("V2").re, 0.5);
+        assert_float_relative_eq!(phasor("I1").re, 1e-3);
+
+        let err = parse_netlist("t\nV1 1 0 SIN(0 1)\n").unwrap_err();
+        assert_eq!(err.message, "SIN needs at least 3 arguments, got 2");
+        let err = parse_netlist("t\nV1 1 0 PWL(0 0 1m)\n").unwrap_err();
+        assert_eq!(err.message, "PWL needs time-value pairs");
+        let err = parse_netlist("t\nV1 1 0 PWL(1m 0 0 1)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+        let err = parse_netlist("t\nV1 1 0 PULSE(0 1 1 1n 1n 1n 1e-17)\n").unwrap_err();
+        assert_eq!(
+            err.message,
+            "PULSE period must be positive and at least rise + width + fall"
+        );
+        assert_eq!((err.line, err.column), (2, 29));
+        let err = parse_netlist("t\nV1 1 0 SFFM(0 1 1meg 2 1k 7)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 27));
+        let err = parse_netlist("t\nV1 1 0 DC 2 SIN(0 1 1k)\n").unwrap_err();
+        assert_eq!(err.message, "SIN cannot follow a DC value");
+        assert_eq!((err.line, err.column), (2, 13));
+        let err = parse_netlist("t\nV1 1 0 2 PULSE(0 1)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 10));
+        let err = parse_netlist("t\nV1 1 0 EXP(0 1 0 -1)\n").unwrap_err();
+        assert_eq!(err.message, "EXP time constants must not be negative");
+        assert_eq!((err.line, err.column), (2, 18));
+        let err = parse_netlist("t\nV1 1 0 PWL FILE=/nonexistent/file\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 17));
+        let err = parse_netlist("t\nV1 1 0 AC\n").unwrap_err();
+        assert_eq!(err.message, "V1 is missing an AC magnitude");
+        let err = parse_netlist("t\nV1 1 0 1 AC 1 0 2\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 17));
+    }
+
+    #[test]
+    fn named_nodes() {
+        let deck = "divider\n\
+                    Vin in GND 10\n\
+                    R1 in Vout 3k\n\
+                    R2 vout 0 1k\n\
+                    R3 vout 100 1k\n\
+                    R4 100 gnd 1meg\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("in").unwrap(), 10.0);
+        let vout = net.get_node_voltage("vout").unwrap();
+        assert_float_absolute_eq!(vout, 2.5, 5e-3);
+        assert_float_relative_eq!(
+            net.get_node_voltage("100").unwrap(),
+            vout * 1e6 / (1e6 + 1e3)
+        );
+    }
+
+    #[test]
+    fn initial_conditions_and_nodesets() {
+        let deck = "rc\n\
+                    V1 in 0 1\n\
+                    R1 in out 1k\n\
+                    C1 out 0 1u\n\
+                    .IC V(out)=0.25\n\
+                    .nodeset v(in)=1 V(out)=500m\n\
+                    + V(in) = 0.9\n";
+        let mut net = parse_netlist(deck).expect("deck should parse");
+        let out = net.node("out");
+        let input = net.node("in");
+        assert_eq!(net.initial_conditions().get(&out), Some(&0.25));
+        assert_eq!(net.nodesets().get(&out), Some(&0.5));
+        assert_eq!(net.nodesets().get(&input), Some(&0.9));
+
+        let err = parse_netlist("t\nR1 1 0 1\n.ic I(1)=1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 5));
+        let err = parse_netlist("t\nR1 1 0 1\n.ic V(0)=1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 7));
+        let err = parse_netlist("t\nR1 1 0 1\n.ic V(1)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 9));
+        let err = parse_netlist("t\nR1 1 0 1\n.nodeset\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 9));
+    }
+
+    #[test]
+    fn error_positions() {
+        let err = parse_netlist("t\nR1 1 0 1k\nR2 1 -x 1k\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 6));
+
+        let err = parse_netlist("t\nV1 1 0 DC 1\nR1 1 0 1q!\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 8));
+
+        let err = parse_netlist("t\nR1 1 0\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 7));
+
+        let err = parse_netlist("t\nR1 1 0\n+ 1k 5\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 6));
+        // Columns count characters, not bytes
+        let err = parse_netlist("t\nR1 1 0\n\u{3000}+ 1k 5\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 7));
+        let err = parse_netlist("t\n\u{3000}+ 1k\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+
+        let err = parse_netlist("t\nQ1 1 2 3 model\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+
+        let err = parse_netlist("t\nV1 1 0 1\n  F1 2 0 V9 1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 10));
+
+        let err = parse_netlist("t\nR1 1 0 1\nr1 1 0 2\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+
+        let err = parse_netlist("t\n.tran 1n 1u\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+
+        let err = parse_netlist("t\n+ 1\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::components::ComponentRef;
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::singularity;
+use crate::solution::Output;
+use nalgebra::base::DMatrix;
+use nalgebra::Complex;
+
+/// Singular values this much smaller than the largest of their matrix, per row, are zero
+const RANK_TOLERANCE: f64 = 1e3 * f64::EPSILON;
+
+/// Poles and zeros of a transfer function, in rad/s, ordered by real and then imaginary part
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoleZeroResult {
+    pub poles: Vec<Complex<f64>>,
+    pub zeros: Vec<Complex<f64>>,
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Poles and zeros of the transfer function from `source` to `output`.
+    ///
+    /// The poles are the values of s at which (A + sE) x = 0 has a solution, where A is the DC
+    /// MNA matrix and E holds the capacitances and inductances. The zeros are those of the same
+    /// system bordered by the source's z direction b and the output's selector c,
+    /// [[A + sE, b], [c^T, 0]]. E is singular whenever a node has no capacitance or a source
+    /// row is present, which gives the pencil infinite eigenvalues; these are deflated exactly
+    /// by eliminating the algebraic equations before any eigenvalues are computed.
+    pub fn pole_zero(
+        &mut self,
+        source: impl Into<ComponentRef>,
+        output: &Output,
+    ) -> Result<PoleZeroResult, SimError> {
+        let source = source.into();
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let a_mat = (*self.a_mat).clone();
+        let e_mat = self.reactive_matrix(&index)?;
+        let (direction, _) = self.source_direction(&index, &source)?;
+        let selector = output.selector(&index, direction.nrows())?;
+
+        let poles = finite_eigenvalues(&a_mat, &e_mat)
+            .ok_or_else(|| SimError::SingularMatrix(self.singularity_report(&a_mat)))?;
+
+        let dim = a_mat.nrows();
+        let mut a_bordered = DMatrix::<f64>::zeros(dim + 1, dim + 1);
+        a_bordered.view_mut((0, 0), (dim, dim)).copy_from(&a_mat);
+        a_bordered
+            .view_mut((0, dim), (dim, 1))
+            .copy_from(&direction);
+        a_bordered
+            .view_mut((dim, 0), (1, dim))
+            .copy_from(&selector.transpose());
+        let mut e_bordered = DMatrix::<f64>::zeros(dim + 1, dim + 1);
+        e_bordered.view_mut((0, 0), (dim, dim)).copy_from(&e_mat);
+        let zeros = finite_eigenvalues(&a_bordered, &e_bordered).ok_or_else(|| {
+            SimError::InvalidAnalysis(
+                "the output does not depend on the source at any frequency".to_string(),
+            )
+        })?;
+
+        Ok(PoleZeroResult { poles, zeros })
+    }
+}
+
+/// The finite s at which A + sE is singular, or None if it is singular for every s.
+///
+/// Rotating with the SVD of E splits the pencil into differential equations (rows where E has
+/// rank) and algebraic ones. The algebraic equations that A22 can solve are eliminated by a
+/// Schur complement. Any that remain are constraints K y1 = 0 on the differential unknowns,
+/// whose multipliers enter through G = A12 restricted to A22's null space; restricting y1 to the
+/// null space of K and projecting out the range of G leaves a smaller pencil with the same
+/// finite eigenvalues, which is reduced again until E is nonsingular.
+fn finite_eigenvalues(a_mat: &DMatrix<f64>, e_mat: &DMatrix<f64>) -> Option<Vec<Complex<f64>>> {
+    let dim = a_mat.nrows();
+    let a_norm = a_mat.norm();
+    let a_tolerance = a_norm * dim.max(1) as f64 * RANK_TOLERANCE;
+    let (u, e_sigma, v) = full_svd(e_mat);
+    let e_tolerance =
+        e_sigma.iter().fold(0.0f64, |acc, &s| acc.max(s)) * dim as f64 * RANK_TOLERANCE;
+    let (u1, u2) = split_columns(&u, &e_sigma, e_tolerance);
+    let (v1, v2) = split_columns(&v, &e_sigma, e_tolerance);
+    let rank = u1.ncols();
+
+    if rank == 0 {
+        // No dynamics: only a regular constant pencil has no eigenvalues at all
+        let lu = a_mat.clone().full_piv_lu();
+        return (dim == 0 || !singularity::is_singular(&lu, a_mat)).then(Vec::new);
+    }
+    let sigma = DMatrix::from_diagonal(&(u1.transpose() * e_mat * &v1).diagonal());
+    let a11 = u1.transpose() * a_mat * &v1;
+    let a12 = u1.transpose() * a_mat * &v2;
+    let a21 = u2.transpose() * a_mat * &v1;
+    let a22 = u2.transpose() * a_mat * &v2;
+
+    // Eliminate the algebraic unknowns A22 determines
+    let (p, a22_sigma, w) = full_svd(&a22);
+    let (p1, p2) = split_columns(&p, &a22_sigma, a_tolerance);
+    let (w1, w2) = split_columns(&w, &a22_sigma, a_tolerance);
+    let s_inv = DMatrix::from_diagonal(&(p1.transpose() * &a22 * &w1).diagonal().map(|s| 1.0 / s));
+    let reduced = a11 - &a12 * &w1 * s_inv * p1.transpose() * &a21;
+    let constraints = p2.ncols();
+    if constraints == 0 {
+        let sigma_inv = DMatrix::from_diagonal(&sigma.diagonal().map(|s| -1.0 / s));
+        let values = (sigma_inv * reduced).complex_eigenvalues();
+        return Some(sorted(values.iter().copied().collect()));
+    }
+
+    // Constraints on y1 with their multipliers: both must have full rank for a regular pencil
+    let g_mat = a12 * w2;
+    let k_mat = p2.transpose() * a21;
+    if constraints > rank {
+        return None;
+    }
+    let left = null_space(&g_mat.transpose(), a_tolerance)?;
+    let right = null_space(&k_mat, a_tolerance)?;
+    if left.ncols() != rank - constraints || right.ncols() != rank - constraints {
+        return None;
+    }
+    finite_eigenvalues(
+        &(left.transpose() * reduced * &right),
+        &(left.transpose() * sigma * &right),
+    )
+}
+
+/// U, the singular values and V of a square matrix
+fn full_svd(matrix: &DMatrix<f64>) -> (DMatrix<f64>, Vec<f64>, DMatrix<f64>) {
+    if matrix.is_empty() {
+        let empty = DMatrix::<f64>::zeros(matrix.nrows(), matrix.ncols());
+        return (empty.clone(), vec![], empty);
+    }
+    let svd = matrix.clone().svd(true, true);
+    let u = svd.u.expect("SVD was asked for U");
+    let v = svd.v_t.expect("SVD was asked for V^T").transpose();
+    (u, svd.singular_values.iter().copied().collect(), v)
+}
+
+/// The columns of `basis` whose singular values are above and at or below `tolerance`
+fn split_columns(
+    basis: &DMatrix<f64>,
+    singular_values: &[f64],
+    tolerance: f64,
+) -> (DMatrix<f64>, DMatrix<f64>) {
+    let (ranked, null): (Vec<usize>, Vec<usize>) =
+        (0..basis.ncols()).partition(|&col| singular_values[col] > tolerance);
+    (basis.select_columns(&ranked), basis.select_columns(&null))
+}
+
+/// An orthonormal basis, as columns, of the right null space of a matrix with at most as many
+/// rows as columns
+fn null_space(matrix: &DMatrix<f64>, tolerance: f64) -> Option<DMatrix<f64>> {
+    let (rows, cols) = matrix.shape();
+    if rows > cols {
+        return None;
+    }
+    // Pad to square so that the SVD returns a full V
+    let mut square = DMatrix::<f64>::zeros(cols, cols);
+    square.view_mut((0, 0), (rows, cols)).copy_from(matrix);
+    let (_, singular_values, v) = full_svd(&square);
+    Some(split_columns(&v, &singular_values, tolerance).1)
+}
+
+/// Orders eigenvalues by real and then imaginary part
+fn sorted(mut values: Vec<Complex<f64>>) -> Vec<Complex<f64>> {
+    values.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+    values
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+    use nalgebra::ComplexField;
+
+    #[test]
+    fn rc_filters() {
+        // Low-pass at node 2 and high-pass at node 3, both with RC = 1 ms
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 0.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-6);
+        let c2 = capacitor::Capacitor::new(1, 3, 1e-6);
+        let r2 = resistor::Resistor::new(3, 0, 1e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Capacitor(c1));
+        net.add_component(Component::Capacitor(c2));
+        net.add_component(Component::Resistor(r2));
+
+        let low = net
+            .pole_zero("V1", &Output::Voltage("2".to_string()))
+            .expect("pole-zero should solve");
+        assert_eq!(low.poles.len(), 2);
+        for pole in &low.poles {
+            assert_float_relative_eq!(pole.re, -1e3, 1e-9);
+            assert_float_absolute_eq!(pole.im, 0.0, 1e-6);
+        }
+        // The high-pass section's pole cancels against a zero at the same place
+        assert_eq!(low.zeros.len(), 1);
+        assert_float_relative_eq!(low.zeros[0].re, -1e3, 1e-9);
+
+        let high = net
+            .pole_zero("V1", &Output::Voltage("3".to_string()))
+            .expect("pole-zero should solve");
+        assert_eq!(high.zeros.len(), 2);
+        assert_float_absolute_eq!(high.zeros[1].modulus(), 0.0, 1e-6);
+        assert_float_relative_eq!(high.zeros[0].re, -1e3, 1e-9);
+    }
+
+    #[test]
+    fn series_rlc() {
+        // Output across C: H(s) = 1 / (LCs^2 + RCs + 1)
+        let (r, l, c) = (10.0, 1e-3, 1e-6);
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 0.0);
+        let r1 = resistor::Resistor::new(1, 2, r);
+        let l1 = inductor::Inductor::new(2, 3, l);
+        let c1 = capacitor::Capacitor::new(3, 0, c);
+        let v1 = net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Inductor(l1));
+        net.add_component(Component::Capacitor(c1));
+
+        let result = net
+            .pole_zero(v1, &Output::Voltage("3".to_string()))
+            .expect("pole-zero should solve");
+        let sigma = -r / (2.0 * l);
+        let omega = (1.0 / (l * c) - sigma * sigma).sqrt();
+        assert_eq!(result.poles.len(), 2);
+        assert_float_relative_eq!(result.poles[0].re, sigma, 1e-9);
+        assert_float_relative_eq!(result.poles[0].im, -omega, 1e-9);
+        assert_float_relative_eq!(result.poles[1].im, omega, 1e-9);
+        assert!(result.zeros.is_empty());
+
+        // Across R the transfer is RCs / (LCs^2 + RCs + 1): one zero at the origin
+        let result = net
+            .pole_zero(v1, &Output::Differential("1".to_string(), "2".to_string()))
+            .expect("pole-zero should solve");
+        assert_eq!(result.zeros.len(), 1);
+        assert_float_absolute_eq!(result.zeros[0].modulus(), 0.0, 1e-6);
+    }
+
+    #[test]
+    fn pole_at_origin() {
+        // G1 cancels R1's conductance, so node 1 has only C1 at DC and A is singular
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 0.0);
+        let c1 = capacitor::Capacitor::new(1, 0, 1e-6);
+        let r1 = resistor::Resistor::new(1, 0, 1e3);
+        let g1 = vc_current_source::VCCurrentSource::new(1, 0, 0, 1, 1e-3);
+        let r2 = resistor::Resistor::new(1, 2, 1e3);
+        let c2 = capacitor::Capacitor::new(2, 0, 1e-6);
+        let i1 = net.add_component(Component::ICurrentSource(i1));
+        net.add_component(Component::Capacitor(c1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::VCCurrentSource(g1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::Capacitor(c2));
+
+        let result = net
+            .pole_zero(i1, &Output::Voltage("1".to_string()))
+            .expect("pole-zero should solve");
+        // Poles at 0 and -2/RC; V(1) = (1 + sRC) / (sC (2 + sRC)) has a zero at -1/RC
+        assert_eq!(result.poles.len(), 2);
+        assert_float_relative_eq!(result.poles[0].re, -2e3, 1e-9);
+        assert_float_absolute_eq!(result.poles[1].modulus(), 0.0, 1e-6);
+        assert_eq!(result.zeros.len(), 1);
+        assert_float_relative_eq!(result.zeros[0].re, -1e3, 1e-9);
+
+        assert!(matches!(
+            net.pole_zero(i1, &Output::Voltage("9".to_string())),
+            Err(SimError::InvalidNode(_))
+        ));
+    }
+}
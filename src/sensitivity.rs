@@ -0,0 +1,299 @@
+use crate::components::{Component, ComponentHandle, Stamp};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use crate::solution::{Output, SolutionIndex};
+use crate::DCComponent;
+use nalgebra::base::DVector;
+use nalgebra::linalg::FullPivLU;
+use nalgebra::Dyn;
+
+/// The derivative of an output with respect to one parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivity {
+    pub parameter: Parameter,
+    /// The parameter's nominal value
+    pub value: f64,
+    /// d(output) / d(parameter)
+    pub absolute: f64,
+    /// The change in the output for a 1% change in the parameter, as SPICE reports it
+    pub normalized: f64,
+}
+
+/// The sensitivity of one output to every resistance, independent source value and
+/// dependent-source gain in the netlist
+#[derive(Debug, Clone)]
+pub struct SensitivityResult {
+    index: SolutionIndex,
+    output: f64,
+    sensitivities: Vec<Sensitivity>,
+}
+
+#[allow(dead_code)]
+impl SensitivityResult {
+    /// The output's value at the operating point
+    pub fn output(&self) -> f64 {
+        self.output
+    }
+
+    /// Every sensitivity, in netlist order
+    pub fn sensitivities(&self) -> &[Sensitivity] {
+        &self.sensitivities
+    }
+
+    /// The sensitivity to `parameter`, whether it names its component by handle or by name
+    pub fn get(&self, parameter: &Parameter) -> Option<&Sensitivity> {
+        let handle = self.index.handle(parameter.component())?;
+        self.sensitivities.iter().find(|sensitivity| {
+            std::mem::discriminant(&sensitivity.parameter) == std::mem::discriminant(parameter)
+                && self.index.handle(sensitivity.parameter.component()) == Some(handle)
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// DC sensitivities of `output` by the adjoint method. With the operating point x from
+    /// A x = z and the adjoint solution A^T λ = c, where c picks the output out of x, the
+    /// derivative with respect to any parameter p is λ^T (dz/dp - dA/dp x). Both solves share
+    /// one factorization of `a_mat`.
+    pub fn sensitivity(&mut self, output: &Output) -> Result<SensitivityResult, SimError> {
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let lu = self.factor_a_mat()?;
+        let x = lu
+            .solve(&self.z_mat.column(0).into_owned())
+            .expect("the factorization was checked for singularity");
+        let selector = output.selector(&index, x.nrows())?;
+        let adjoint = solve_transpose(&lu, &selector);
+
+        let mut sensitivities = Vec::new();
+        for (idx, component) in self.components().iter().enumerate() {
+            let handle = ComponentHandle(idx);
+            let (parameter, absolute) = match component {
+                Component::Resistor(res) => {
+                    // G holds 1/R, so dA/dR is the unit-conductance stamp scaled by -1/R^2
+                    let mut unit = component.clone();
+                    let parameter = Parameter::Resistance(handle.into());
+                    parameter.set(&mut unit, 1.0);
+                    let product = stamp_product(&index, &unit, &x)?;
+                    (
+                        parameter,
+                        adjoint.dot(&product) / (res.resistance * res.resistance),
+                    )
+                }
+                Component::IVoltageSource(_) | Component::ICurrentSource(_) => {
+                    let parameter = Parameter::SourceValue(handle.into());
+                    let (direction, _) = self.source_direction(&index, &handle.into())?;
+                    (parameter, adjoint.dot(&direction))
+                }
+                Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_) => {
+                    // The stamps are affine in the gain
+                    let parameter = Parameter::Gain(handle.into());
+                    let mut unit = component.clone();
+                    let mut zero = component.clone();
+                    parameter.set(&mut unit, 1.0);
+                    parameter.set(&mut zero, 0.0);
+                    let product =
+                        stamp_product(&index, &unit, &x)? - stamp_product(&index, &zero, &x)?;
+                    (parameter, -adjoint.dot(&product))
+                }
+                // Open and short circuits at DC, whatever their value
+                Component::Capacitor(_) | Component::Inductor(_) => continue,
+            };
+            let value = self
+                .parameter(&parameter)
+                .expect("every sensitivity is of a parameter of its component");
+            sensitivities.push(Sensitivity {
+                parameter,
+                value,
+                absolute,
+                normalized: absolute * value / 100.0,
+            });
+        }
+
+        Ok(SensitivityResult {
+            output: selector.dot(&x),
+            index,
+            sensitivities,
+        })
+    }
+}
+
+/// Solves A^T x = b with the factorization P A Q = L U of A
+pub(crate) fn solve_transpose(lu: &FullPivLU<f64, Dyn, Dyn>, b: &DVector<f64>) -> DVector<f64> {
+    let mut x = b.clone();
+    lu.q().permute_rows(&mut x);
+    lu.u().tr_solve_upper_triangular_mut(&mut x);
+    lu.l().tr_solve_lower_triangular_mut(&mut x);
+    lu.p().inv_permute_rows(&mut x);
+    x
+}
+
+/// The product of one component's contribution to `a_mat` with `x`
+fn stamp_product(
+    index: &SolutionIndex,
+    component: &Component,
+    x: &DVector<f64>,
+) -> Result<DVector<f64>, SimError> {
+    let row = |id: usize| index.row_of_id(id as u64);
+    let aux = |source_num: usize| index.aux_row(source_num as u64);
+    let mut product = DVector::<f64>::zeros(x.nrows());
+    for Stamp(r, c, val) in component.get_gmat_stamps() {
+        product[row(r)?] += val * x[row(c)?];
+    }
+    for Stamp(r, c, val) in component.get_bmat_stamps() {
+        product[row(r)?] += val * x[aux(c)];
+    }
+    for Stamp(r, c, val) in component.get_cmat_stamps() {
+        product[aux(r)] += val * x[row(c)?];
+    }
+    for Stamp(r, c, val) in component.get_dmat_stamps() {
+        product[aux(r)] += val * x[aux(c)];
+    }
+    Ok(product)
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn transpose_solve() {
+        let a =
+            nalgebra::DMatrix::from_row_slice(3, 3, &[0.0, 2.0, 1.0, 3.0, 1.0, 0.0, 1.0, 4.0, 5.0]);
+        let b = DVector::from_vec(vec![1.0, -2.0, 0.5]);
+        let x = solve_transpose(&a.clone().full_piv_lu(), &b);
+        assert!((a.transpose() * x - b).norm() < 1e-12);
+    }
+
+    #[test]
+    fn divider_sensitivities() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 10.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 3e3);
+        let i1 = independent_current_source::ICurrentSource::new(0, 2, 1e-3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-9);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("R2", Component::Resistor(r2))
+            .expect("new name");
+        net.add_named_component("I1", Component::ICurrentSource(i1))
+            .expect("new name");
+        net.add_component(Component::Capacitor(c1));
+
+        // V(2) = (V1 R2 + I1 R1 R2) / (R1 + R2)
+        let result = net
+            .sensitivity(&Output::Voltage("2".to_string()))
+            .expect("sensitivities should solve");
+        assert_float_relative_eq!(result.output(), 8.25);
+        assert_eq!(result.sensitivities().len(), 4);
+        let (v, i, r1, r2) = (10.0f64, 1e-3, 1e3, 3e3);
+        let sum = r1 + r2;
+        let expected = [
+            ("V1", Parameter::SourceValue("V1".into()), r2 / sum),
+            ("I1", Parameter::SourceValue("I1".into()), r1 * r2 / sum),
+            (
+                "R1",
+                Parameter::Resistance("R1".into()),
+                (i * r2 * r2 - v * r2) / sum.powi(2),
+            ),
+            (
+                "R2",
+                Parameter::Resistance("R2".into()),
+                (v * r1 + i * r1 * r1) / sum.powi(2),
+            ),
+        ];
+        for (name, parameter, derivative) in expected {
+            let sensitivity = result.get(&parameter).unwrap_or_else(|| panic!("{}", name));
+            assert_float_relative_eq!(sensitivity.absolute, derivative, 1e-9);
+            assert_float_relative_eq!(
+                sensitivity.normalized,
+                derivative * sensitivity.value / 100.0,
+                1e-9
+            );
+        }
+        assert_eq!(result.get(&Parameter::Gain("R1".into())), None);
+    }
+
+    #[test]
+    fn gain_and_current_outputs() {
+        // E1 buffers V(1) with gain k into R2, so I(V1) = -V1/R1 and V(2) = k V1
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 2.0);
+        let r1 = resistor::Resistor::new(1, 0, 4.0);
+        let e1 = vc_voltage_source::VCVoltageSource::new(1, 0, 2, 0, 3.0);
+        let r2 = resistor::Resistor::new(2, 0, 1.0);
+        let f1 = cc_current_source::CCCurrentSource::new("V1", 0, 3, 0.5);
+        let r3 = resistor::Resistor::new(3, 0, 2.0);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("E1", Component::VCVoltageSource(e1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r2));
+        net.add_named_component("F1", Component::CCCurrentSource(f1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r3));
+
+        let result = net
+            .sensitivity(&Output::Voltage("2".to_string()))
+            .expect("sensitivities should solve");
+        let gain = result.get(&Parameter::Gain("E1".into())).unwrap();
+        assert_float_relative_eq!(gain.absolute, 2.0);
+        assert_float_relative_eq!(
+            result
+                .get(&Parameter::SourceValue("V1".into()))
+                .unwrap()
+                .absolute,
+            3.0
+        );
+        assert_float_absolute_eq!(
+            result
+                .get(&Parameter::Resistance("R1".into()))
+                .unwrap()
+                .absolute,
+            0.0
+        );
+
+        let result = net
+            .sensitivity(&Output::Current("V1".into()))
+            .expect("sensitivities should solve");
+        assert_float_relative_eq!(result.output(), -0.5);
+        assert_float_relative_eq!(
+            result
+                .get(&Parameter::Resistance("R1".into()))
+                .unwrap()
+                .absolute,
+            2.0 / 16.0
+        );
+
+        // Compare the CCCS gain against a finite difference
+        let output = Output::Voltage("3".to_string());
+        let result = net
+            .sensitivity(&output)
+            .expect("sensitivities should solve");
+        let gain = Parameter::Gain("F1".into());
+        let perturbed = net
+            .with_parameter(&gain, 0.5 + 1e-6, |net| net.sensitivity(&output))
+            .expect("sensitivities should solve")
+            .output();
+        assert_float_relative_eq!(
+            result.get(&gain).unwrap().absolute,
+            (perturbed - result.output()) / 1e-6,
+            1e-5
+        );
+    }
+}
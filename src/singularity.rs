@@ -0,0 +1,203 @@
+use crate::components::ComponentHandle;
+use crate::netlist::Netlist;
+use nalgebra::base::DMatrix;
+use nalgebra::linalg::FullPivLU;
+use nalgebra::{ComplexField, Dyn};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Which unknowns and equations of a singular MNA system are responsible for the singularity
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SingularityReport {
+    /// How many more independent equations the system would need to be solvable
+    pub rank_deficiency: usize,
+    /// Nodes whose voltage the circuit does not determine
+    pub floating_nodes: Vec<String>,
+    /// Sources whose branch currents are undetermined or whose constraints contradict each
+    /// other, e.g. dependent sources that fix the same voltage
+    pub conflicting_sources: Vec<String>,
+}
+
+impl fmt::Display for SingularityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rank deficient by {}", self.rank_deficiency)?;
+        if !self.floating_nodes.is_empty() {
+            write!(f, "; floating node(s) {}", self.floating_nodes.join(", "))?;
+        }
+        if !self.conflicting_sources.is_empty() {
+            write!(
+                f,
+                "; conflicting source(s) {}",
+                self.conflicting_sources.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Relative tolerance below which a pivot or singular value is treated as zero
+fn rank_tolerance<T: ComplexField>(a_mat: &DMatrix<T>, largest: f64) -> f64 {
+    largest * a_mat.nrows().max(1) as f64 * f64::EPSILON
+}
+
+/// True if the factored matrix is singular to working precision. nalgebra only refuses to solve
+/// when the last pivot is exactly zero, which round-off rarely produces.
+pub(crate) fn is_singular<T: ComplexField<RealField = f64>>(
+    lu: &FullPivLU<T, Dyn, Dyn>,
+    a_mat: &DMatrix<T>,
+) -> bool {
+    if a_mat.is_empty() {
+        return false;
+    }
+    // Full pivoting leaves the largest pivot first and the smallest last
+    let u = lu.u();
+    let dim = u.nrows();
+    let largest = u[(0, 0)].clone().modulus();
+    u[(dim - 1, dim - 1)].clone().modulus() <= rank_tolerance(a_mat, largest)
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Maps the null spaces of a singular MNA matrix back onto the circuit. Unknowns in the right
+    /// null space are node voltages and branch currents the equations leave free; equations in
+    /// the left null space are the KCL rows and source constraints that depend on each other.
+    pub(crate) fn singularity_report(&self, a_mat: &DMatrix<f64>) -> SingularityReport {
+        let dim = a_mat.nrows();
+        let n = self.node_table().num_rows();
+        let svd = a_mat.clone().svd(true, true);
+        let u = svd.u.as_ref().expect("SVD was asked for U");
+        let v_t = svd.v_t.as_ref().expect("SVD was asked for V^T");
+        let largest = svd.singular_values.max();
+        let tolerance = rank_tolerance(a_mat, largest);
+
+        let mut floating_rows = BTreeSet::new();
+        let mut conflicting_aux = BTreeSet::new();
+        let mut rank_deficiency = 0;
+        for (idx, &sigma) in svd.singular_values.iter().enumerate() {
+            if sigma > tolerance {
+                continue;
+            }
+            rank_deficiency += 1;
+
+            // Right null vector: free unknowns
+            let right = v_t.row(idx).transpose();
+            for row in significant_entries(right.as_slice()) {
+                if row < n {
+                    floating_rows.insert(row);
+                } else {
+                    conflicting_aux.insert(row - n);
+                }
+            }
+
+            // Left null vector: dependent equations. Only the source rows are reported, since a
+            // dependent KCL row is the current-source cut-set that validation already catches.
+            let left = u.column(idx).into_owned();
+            for row in significant_entries(left.as_slice()) {
+                if row >= n {
+                    conflicting_aux.insert(row - n);
+                }
+            }
+        }
+        debug_assert!(rank_deficiency <= dim);
+
+        let floating_nodes = floating_rows
+            .into_iter()
+            .map(|row| {
+                let id = self
+                    .node_table()
+                    .id_at_row(row)
+                    .expect("every node row has an id");
+                self.node_table().name_of(id)
+            })
+            .collect();
+        let conflicting_sources = conflicting_aux
+            .into_iter()
+            .filter_map(|aux| {
+                self.components()
+                    .iter()
+                    .position(|component| component.source_num() == Some(aux as u64 + 1))
+                    .map(|idx| self.component_label(ComponentHandle(idx)))
+            })
+            .collect();
+
+        SingularityReport {
+            rank_deficiency,
+            floating_nodes,
+            conflicting_sources,
+        }
+    }
+}
+
+/// Indices of the entries of a unit vector that are not round-off noise
+fn significant_entries(vector: &[f64]) -> Vec<usize> {
+    let largest = vector.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    vector
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.abs() > largest * 1e-6)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::error::SimError;
+
+    #[test]
+    fn floating_node() {
+        // The VCCS exactly cancels the resistor's conductance, leaving node 2 undetermined
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let r2 = resistor::Resistor::new(2, 0, 2.0);
+        let g1 = vc_current_source::VCCurrentSource::new(2, 0, 0, 2, 0.5);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::VCCurrentSource(g1));
+
+        net.initialize_dc_mna().expect("topology is valid");
+        assert_eq!(
+            net.solve_dc_mna(),
+            Err(SimError::SingularMatrix(SingularityReport {
+                rank_deficiency: 1,
+                floating_nodes: vec!["2".to_string()],
+                conflicting_sources: vec![],
+            }))
+        );
+    }
+
+    #[test]
+    fn conflicting_sources() {
+        // E1 forces V(2) = V(1) and E2 forces V(1) = V(2), so neither voltage is determined
+        let mut net = Netlist::new();
+
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let r2 = resistor::Resistor::new(2, 0, 1.0);
+        let e1 = vc_voltage_source::VCVoltageSource::new(1, 0, 2, 0, 1.0);
+        let e2 = vc_voltage_source::VCVoltageSource::new(2, 0, 1, 0, 1.0);
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("R2", Component::Resistor(r2))
+            .expect("new name");
+        net.add_named_component("E1", Component::VCVoltageSource(e1))
+            .expect("new name");
+        net.add_named_component("E2", Component::VCVoltageSource(e2))
+            .expect("new name");
+
+        net.initialize_dc_mna().expect("topology is valid");
+        let Err(SimError::SingularMatrix(report)) = net.solve_dc_mna() else {
+            panic!("solve should fail");
+        };
+        assert_eq!(report.rank_deficiency, 1);
+        assert_eq!(report.floating_nodes, vec!["1", "2"]);
+        assert_eq!(report.conflicting_sources, vec!["E1", "E2"]);
+        assert_eq!(
+            report.to_string(),
+            "rank deficient by 1; floating node(s) 1, 2; conflicting source(s) E1, E2"
+        );
+    }
+}
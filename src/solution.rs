@@ -0,0 +1,111 @@
+use crate::components::{ComponentHandle, ComponentRef};
+use crate::error::SimError;
+use crate::node_table::NodeTable;
+use nalgebra::base::DVector;
+use std::collections::HashMap;
+
+/// A scalar that an analysis measures from a solution, as in SPICE's `V(out)`, `V(a,b)` and
+/// `I(Vsrc)`
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Voltage(String),
+    /// The voltage of the first node relative to the second
+    Differential(String, String),
+    /// The branch current of a voltage source or inductor
+    Current(ComponentRef),
+}
+
+#[allow(dead_code)]
+impl Output {
+    /// The vector whose dot product with a solution gives this output
+    pub(crate) fn selector(
+        &self,
+        index: &SolutionIndex,
+        dim: usize,
+    ) -> Result<DVector<f64>, SimError> {
+        let mut selector = DVector::<f64>::zeros(dim);
+        match self {
+            Output::Voltage(name) => selector[index.node_row(name)?] = 1.0,
+            Output::Differential(positive, negative) => {
+                // Either node may be ground, which has no row
+                if !NodeTable::is_ground(positive) {
+                    selector[index.node_row(positive)?] += 1.0;
+                }
+                if !NodeTable::is_ground(negative) {
+                    selector[index.node_row(negative)?] -= 1.0;
+                }
+            }
+            Output::Current(component) => selector[index.branch_row(component.clone())?] = 1.0,
+        }
+        Ok(selector)
+    }
+
+    /// The value of this output in solution `x`
+    pub fn value(&self, index: &SolutionIndex, x: &DVector<f64>) -> Result<f64, SimError> {
+        Ok(self.selector(index, x.nrows())?.dot(x))
+    }
+}
+
+/// Maps node names and components onto rows of the MNA solution vector. Analyses that return
+/// many solutions keep one of these so results can be queried after the netlist has changed.
+#[derive(Debug, Clone)]
+pub struct SolutionIndex {
+    pub(crate) nodes: NodeTable,
+    pub(crate) num_nodes: usize,
+    pub(crate) component_names: HashMap<String, ComponentHandle>,
+    pub(crate) source_nums: Vec<Option<u64>>,
+}
+
+#[allow(dead_code)]
+impl SolutionIndex {
+    /// The row holding the voltage of the node called `name`
+    pub fn node_row(&self, name: &str) -> Result<usize, SimError> {
+        self.nodes
+            .row_of(name)
+            .ok_or_else(|| SimError::InvalidNode(name.to_string()))
+    }
+
+    /// The row holding the branch current of a voltage source or inductor
+    pub fn branch_row(&self, component: impl Into<ComponentRef>) -> Result<usize, SimError> {
+        let component = component.into();
+        self.handle(&component)
+            .and_then(|handle| self.source_nums.get(handle.0).copied().flatten())
+            .map(|source_num| self.aux_row(source_num))
+            .ok_or(SimError::InvalidSourceReference(component))
+    }
+
+    /// The handle `component` refers to, if it names a component
+    pub(crate) fn handle(&self, component: &ComponentRef) -> Option<ComponentHandle> {
+        match component {
+            ComponentRef::Handle(handle) => Some(*handle),
+            ComponentRef::Name(name) => self.component_names.get(name).copied(),
+        }
+    }
+
+    /// The row of the node with id `id`, which must not be ground
+    pub(crate) fn row_of_id(&self, id: u64) -> Result<usize, SimError> {
+        self.nodes
+            .row(id)
+            .ok_or_else(|| SimError::InvalidNode(self.nodes.name_of(id)))
+    }
+
+    /// The row of auxiliary variable `source_num` (1-based)
+    pub(crate) fn aux_row(&self, source_num: u64) -> usize {
+        self.num_nodes + source_num as usize - 1
+    }
+
+    /// Voltage of node `id` in solution `x`, with ground at 0V
+    pub(crate) fn voltage(&self, x: &DVector<f64>, id: u64) -> f64 {
+        self.nodes.row(id).map_or(0.0, |row| x[row])
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    /// The length of a solution vector: one row per node and per auxiliary variable
+    pub fn dim(&self) -> usize {
+        self.num_nodes + self.source_nums.iter().flatten().count()
+    }
+}
@@ -0,0 +1,195 @@
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use crate::sweep::Sweep;
+
+/// The results of one analysis rerun at each value of a stepped parameter
+#[derive(Debug, Clone)]
+pub struct StepResult<T> {
+    parameter: Parameter,
+    values: Vec<f64>,
+    results: Vec<T>,
+}
+
+#[allow(dead_code)]
+impl<T> StepResult<T> {
+    /// The stepped parameter
+    pub fn parameter(&self) -> &Parameter {
+        &self.parameter
+    }
+
+    /// The parameter values, in step order
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// The analysis results, in step order
+    pub fn results(&self) -> &[T] {
+        &self.results
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The result for the step whose parameter value is `value`. Values generated by a range
+    /// sweep carry round-off, so they match to a relative tolerance.
+    pub fn get(&self, value: f64) -> Option<&T> {
+        let tolerance = value.abs() * 1e-9;
+        self.values
+            .iter()
+            .position(|&stepped| (stepped - value).abs() <= tolerance)
+            .map(|step| &self.results[step])
+    }
+
+    /// Pairs of parameter value and result, in step order
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &T)> {
+        self.values.iter().copied().zip(self.results.iter())
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Reruns `analysis` with `parameter` set to each value of `sweep` in turn, like SPICE's
+    /// `.step`. Any analysis can be stepped, e.g. `|net| net.transient(1e-3, 1e-6)`. The
+    /// netlist is restored once the last step finishes, and the first failing step aborts the
+    /// run with its error.
+    pub fn step<T>(
+        &mut self,
+        parameter: &Parameter,
+        sweep: &Sweep,
+        mut analysis: impl FnMut(&mut Netlist) -> Result<T, SimError>,
+    ) -> Result<StepResult<T>, SimError> {
+        let values = sweep.values()?;
+        // Fail before running anything if the parameter does not exist
+        self.parameter(parameter)?;
+        let results = values
+            .iter()
+            .map(|&value| self.with_parameter(parameter, value, &mut analysis))
+            .collect::<Result<_, _>>()?;
+        Ok(StepResult {
+            parameter: parameter.clone(),
+            values,
+            results,
+        })
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::waveform::Waveform;
+    use assert_float_eq::*;
+
+    #[allow(dead_code)]
+    fn divider() -> (Netlist, ComponentHandle) {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 10.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 1e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        let r2 = net.add_component(Component::Resistor(r2));
+        (net, r2)
+    }
+
+    #[test]
+    fn step_resistance() {
+        let (mut net, r2) = divider();
+        let resistance = Parameter::Resistance(r2.into());
+        let result = net
+            .step(&resistance, &Sweep::List(vec![1e3, 2.2e3, 4.7e3]), |net| {
+                net.initialize_dc_mna()?;
+                net.solve_dc_mna()?;
+                net.get_node_voltage("2")
+            })
+            .expect("steps should run");
+        assert_eq!(result.len(), 3);
+        assert_eq!(result.parameter(), &resistance);
+        for (r, &v) in result.iter() {
+            assert_float_relative_eq!(v, 10.0 * r / (1e3 + r));
+        }
+        assert_float_relative_eq!(*result.get(2.2e3).unwrap(), 10.0 * 2.2 / 3.2);
+        assert_eq!(result.get(3e3), None);
+        assert_eq!(net.parameter(&resistance), Ok(1e3));
+    }
+
+    #[test]
+    fn step_any_analysis() {
+        let (mut net, r2) = divider();
+        let resistance = Parameter::Resistance(r2.into());
+        let result = net
+            .step(&resistance, &Sweep::List(vec![1e3, 3e3]), |net| {
+                net.dc_sweep("V1", &Sweep::List(vec![0.0, 4.0]))
+            })
+            .expect("steps should run");
+        assert_eq!(
+            result.get(1e3).unwrap().node_voltage("2"),
+            Ok(vec![0.0, 2.0])
+        );
+        assert_eq!(
+            result.get(3e3).unwrap().node_voltage("2"),
+            Ok(vec![0.0, 3.0])
+        );
+
+        let value = Parameter::SourceValue("V1".into());
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 3.0,
+            points: 3,
+        };
+        let result = net
+            .step(&value, &sweep, |net| net.transient(1e-3, 1e-4))
+            .expect("steps should run");
+        let last = result.get(3.0).unwrap();
+        assert_float_relative_eq!(*last.node_voltage("2").unwrap().last().unwrap(), 1.5);
+
+        let missing = Parameter::Gain("E1".into());
+        assert_eq!(
+            net.step(&missing, &sweep, |_| Ok(())).unwrap_err(),
+            SimError::InvalidParameter(missing.clone())
+        );
+    }
+
+    #[test]
+    fn step_waveform_source() {
+        let mut net = Netlist::new();
+        let sin = Waveform::Sin {
+            offset: 0.0,
+            amplitude: 1.0,
+            frequency: 1e3,
+            delay: 0.0,
+            damping: 0.0,
+            phase: 0.0,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, sin);
+        let r1 = resistor::Resistor::new(1, 0, 1e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+
+        // Stepping the value moves the sinusoid's offset and keeps it swinging
+        let value = Parameter::SourceValue("V1".into());
+        let result = net
+            .step(&value, &Sweep::List(vec![0.0, 2.0]), |net| {
+                net.transient(1e-3, 1e-5)
+            })
+            .expect("steps should run");
+        for (offset, tran) in result.iter() {
+            let voltages = tran.node_voltage("1").unwrap();
+            for (&t, &v) in tran.time().iter().zip(&voltages) {
+                let expected = offset + (2.0 * std::f64::consts::PI * 1e3 * t).sin();
+                assert_float_absolute_eq!(v, expected, 1e-9);
+            }
+            let max = voltages.iter().copied().fold(f64::MIN, f64::max);
+            assert_float_absolute_eq!(max, offset + 1.0, 1e-2);
+        }
+        assert_eq!(net.parameter(&value), Ok(0.0));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::SimError;
+
+/// The points of a swept analysis, as in SPICE's `LIN`, `DEC` and `OCT` sweeps or an explicit
+/// `LIST`. Logarithmic sweeps include `stop` when it falls on a point.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sweep {
+    /// `points` evenly spaced values from `start` to `stop` inclusive
+    Linear {
+        start: f64,
+        stop: f64,
+        points: usize,
+    },
+    /// `points_per_decade` logarithmically spaced values per factor of ten
+    Decade {
+        start: f64,
+        stop: f64,
+        points_per_decade: usize,
+    },
+    /// `points_per_octave` logarithmically spaced values per factor of two
+    Octave {
+        start: f64,
+        stop: f64,
+        points_per_octave: usize,
+    },
+    /// The given values, in the given order
+    List(Vec<f64>),
+}
+
+#[allow(dead_code)]
+impl Sweep {
+    pub fn values(&self) -> Result<Vec<f64>, SimError> {
+        match self {
+            Sweep::Linear {
+                start,
+                stop,
+                points,
+            } => match points {
+                0 => Err(SimError::InvalidAnalysis(
+                    "a linear sweep needs at least one point".to_string(),
+                )),
+                1 => Ok(vec![*start]),
+                _ => Ok((0..*points)
+                    .map(|k| start + (stop - start) * k as f64 / (*points - 1) as f64)
+                    .collect()),
+            },
+            Sweep::Decade {
+                start,
+                stop,
+                points_per_decade,
+            } => log_sweep(*start, *stop, 10.0, *points_per_decade),
+            Sweep::Octave {
+                start,
+                stop,
+                points_per_octave,
+            } => log_sweep(*start, *stop, 2.0, *points_per_octave),
+            Sweep::List(values) if values.is_empty() => Err(SimError::InvalidAnalysis(
+                "a list sweep needs at least one value".to_string(),
+            )),
+            Sweep::List(values) => Ok(values.clone()),
+        }
+    }
+}
+
+fn log_sweep(
+    start: f64,
+    stop: f64,
+    base: f64,
+    points_per_step: usize,
+) -> Result<Vec<f64>, SimError> {
+    if !(start > 0.0 && stop >= start && points_per_step > 0) {
+        return Err(SimError::InvalidAnalysis(format!(
+            "a logarithmic sweep needs 0 < start <= stop and at least one point per step, got \
+             start={}, stop={} and {} points",
+            start, stop, points_per_step
+        )));
+    }
+    let steps = (stop / start).log(base) * points_per_step as f64;
+    // Allow for round-off so that e.g. 1 to 1000 includes 1000
+    let count = (steps + 1e-9).floor() as usize + 1;
+    Ok((0..count)
+        .map(|k| start * base.powf(k as f64 / points_per_step as f64))
+        .collect())
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn linear() {
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 2.0,
+            points: 5,
+        };
+        assert_eq!(sweep.values().unwrap(), vec![1.0, 1.25, 1.5, 1.75, 2.0]);
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 2.0,
+            points: 0,
+        };
+        assert!(sweep.values().is_err());
+    }
+
+    #[test]
+    fn logarithmic() {
+        let decade = Sweep::Decade {
+            start: 10.0,
+            stop: 1e4,
+            points_per_decade: 2,
+        };
+        let values = decade.values().unwrap();
+        assert_eq!(values.len(), 7);
+        assert_float_relative_eq!(values[1], 10.0 * 10f64.sqrt());
+        assert_float_relative_eq!(values[6], 1e4);
+
+        let octave = Sweep::Octave {
+            start: 1.0,
+            stop: 10.0,
+            points_per_octave: 1,
+        };
+        assert_eq!(octave.values().unwrap(), vec![1.0, 2.0, 4.0, 8.0]);
+
+        let invalid = Sweep::Decade {
+            start: 0.0,
+            stop: 1.0,
+            points_per_decade: 10,
+        };
+        assert!(matches!(
+            invalid.values(),
+            Err(SimError::InvalidAnalysis(_))
+        ));
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(
+            Sweep::List(vec![3.0, 1.0]).values().unwrap(),
+            vec![3.0, 1.0]
+        );
+        assert!(Sweep::List(vec![]).values().is_err());
+    }
+}
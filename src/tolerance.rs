@@ -0,0 +1,228 @@
+use crate::components::{Component, ComponentHandle};
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use std::collections::HashMap;
+
+/// How a toleranced value is spread about its nominal value
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Distribution {
+    /// Equally likely anywhere within the tolerance
+    #[default]
+    Uniform,
+    /// Normally distributed with the tolerance as the 3-sigma bound. Samples are not clipped.
+    Gaussian,
+}
+
+/// The relative spread of a component value, e.g. 0.05 for a 5% resistor.
+///
+/// The device tolerance is drawn independently for every component. Components that name the
+/// same lot also share one lot deviation per trial, which models parts from one reel tracking
+/// each other; the two deviations add.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tolerance {
+    pub device: f64,
+    pub distribution: Distribution,
+    pub lot: Option<String>,
+    pub lot_tolerance: f64,
+}
+
+#[allow(dead_code)]
+impl Tolerance {
+    pub fn uniform(device: f64) -> Self {
+        Self {
+            device,
+            distribution: Distribution::Uniform,
+            ..Default::default()
+        }
+    }
+
+    pub fn gaussian(device: f64) -> Self {
+        Self {
+            device,
+            distribution: Distribution::Gaussian,
+            ..Default::default()
+        }
+    }
+
+    /// Correlates this component with every other in lot `name`
+    pub fn with_lot(mut self, name: &str, lot_tolerance: f64) -> Self {
+        self.lot = Some(name.to_string());
+        self.lot_tolerance = lot_tolerance;
+        self
+    }
+
+    /// The largest relative deviation a uniform sample can reach
+    pub fn bound(&self) -> f64 {
+        self.device + self.lot_tolerance
+    }
+
+    /// A relative deviation within `tolerance`, drawn from this tolerance's distribution
+    fn deviation(&self, tolerance: f64, rng: &mut Rng) -> f64 {
+        match self.distribution {
+            Distribution::Uniform => tolerance * (2.0 * rng.uniform() - 1.0),
+            Distribution::Gaussian => tolerance / 3.0 * rng.gaussian(),
+        }
+    }
+}
+
+/// A small seeded generator (SplitMix64), so that Monte Carlo runs are reproducible without an
+/// external dependency
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+#[allow(dead_code)]
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform on [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal, by the Box-Muller transform
+    pub fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+#[allow(dead_code)]
+impl Component {
+    /// The tolerance of the component's main value, for the kinds that can carry one
+    pub fn tolerance(&self) -> Option<&Tolerance> {
+        match self {
+            Component::Resistor(res) => res.tolerance.as_ref(),
+            Component::Capacitor(cap) => cap.tolerance.as_ref(),
+            Component::Inductor(ind) => ind.tolerance.as_ref(),
+            Component::IVoltageSource(vs) => vs.tolerance.as_ref(),
+            Component::ICurrentSource(is) => is.tolerance.as_ref(),
+            Component::VCCurrentSource(_)
+            | Component::CCCurrentSource(_)
+            | Component::CCVoltageSource(_)
+            | Component::VCVoltageSource(_) => None,
+        }
+    }
+
+    /// The parameter a tolerance on this component applies to
+    fn toleranced_parameter(&self, handle: ComponentHandle) -> Option<Parameter> {
+        match self {
+            Component::Resistor(_) => Some(Parameter::Resistance(handle.into())),
+            Component::Capacitor(_) => Some(Parameter::Capacitance(handle.into())),
+            Component::Inductor(_) => Some(Parameter::Inductance(handle.into())),
+            Component::IVoltageSource(_) | Component::ICurrentSource(_) => {
+                Some(Parameter::SourceScale(handle.into()))
+            }
+            Component::VCCurrentSource(_)
+            | Component::CCCurrentSource(_)
+            | Component::CCVoltageSource(_)
+            | Component::VCVoltageSource(_) => None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Every toleranced parameter with its nominal value and tolerance, in netlist order.
+    /// Source tolerances scale the whole waveform, so their nominal value is 1.
+    pub fn toleranced_parameters(&self) -> Vec<(Parameter, f64, Tolerance)> {
+        self.components()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, component)| {
+                let tolerance = component.tolerance()?;
+                let parameter = component.toleranced_parameter(ComponentHandle(idx))?;
+                let nominal = self
+                    .parameter(&parameter)
+                    .expect("every toleranced component has the parameter");
+                Some((parameter, nominal, tolerance.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Draws one set of values for `parameters`, as returned by `toleranced_parameters`
+pub(crate) fn sample_parameters(
+    parameters: &[(Parameter, f64, Tolerance)],
+    rng: &mut Rng,
+) -> Vec<(Parameter, f64)> {
+    let mut lots: HashMap<&str, f64> = HashMap::new();
+    parameters
+        .iter()
+        .map(|(parameter, nominal, tolerance)| {
+            let lot = match &tolerance.lot {
+                Some(name) => *lots
+                    .entry(name)
+                    .or_insert_with(|| tolerance.deviation(tolerance.lot_tolerance, rng)),
+                None => 0.0,
+            };
+            let device = tolerance.deviation(tolerance.device, rng);
+            (parameter.clone(), nominal * (1.0 + lot + device))
+        })
+        .collect()
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn reproducible_generator() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let samples: Vec<f64> = (0..1000).map(|_| a.uniform()).collect();
+        assert!(samples.iter().all(|&u| (0.0..1.0).contains(&u)));
+        assert!(samples
+            .iter()
+            .zip((0..1000).map(|_| b.uniform()))
+            .all(|(x, y)| *x == y));
+        assert_ne!(Rng::new(8).next_u64(), Rng::new(7).next_u64());
+
+        let normal: Vec<f64> = (0..20000).map(|_| a.gaussian()).collect();
+        let mean = normal.iter().sum::<f64>() / normal.len() as f64;
+        let var = normal.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / normal.len() as f64;
+        assert_float_absolute_eq!(mean, 0.0, 0.03);
+        assert_float_absolute_eq!(var, 1.0, 0.05);
+    }
+
+    #[test]
+    fn lot_correlation() {
+        let mut net = Netlist::new();
+        let lot = Tolerance::uniform(0.0).with_lot("reel", 0.1);
+        let r1 = resistor::Resistor::new(1, 0, 1e3).with_tolerance(lot.clone());
+        let r2 = resistor::Resistor::new(1, 0, 2e3).with_tolerance(lot);
+        let r3 = resistor::Resistor::new(1, 0, 3e3).with_tolerance(Tolerance::uniform(0.05));
+        let r4 = resistor::Resistor::new(1, 0, 4e3);
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::Resistor(r3));
+        net.add_component(Component::Resistor(r4));
+
+        let parameters = net.toleranced_parameters();
+        assert_eq!(parameters.len(), 3);
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let values = sample_parameters(&parameters, &mut rng);
+            // Same lot, no device spread: the two resistors move together
+            assert_float_relative_eq!(values[1].1 / values[0].1, 2.0);
+            assert!((0.9e3..=1.1e3).contains(&values[0].1));
+            assert!((2.85e3..=3.15e3).contains(&values[2].1));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::components::{Component, ComponentRef};
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::solution::Output;
+
+/// Small-signal DC transfer characteristics between an independent source and an output, as
+/// SPICE's `.tf` reports them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFunction {
+    /// d(output) / d(source value)
+    pub gain: f64,
+    /// The resistance the source sees looking into the circuit
+    pub input_resistance: f64,
+    /// The Thevenin resistance at the output port: between the two nodes of a voltage output,
+    /// or in series with the branch of a current output
+    pub output_resistance: f64,
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Computes the transfer function from `source` to `output` with one factorization of
+    /// `a_mat`. Each quantity is the response to a unit excitation: of the source's value for
+    /// the gain and input resistance, and of a test current (or, for a current output, a test
+    /// voltage in the branch) at the output port with the other sources held constant. An open
+    /// input or output gives an infinite resistance.
+    pub fn transfer_function(
+        &mut self,
+        source: impl Into<ComponentRef>,
+        output: &Output,
+    ) -> Result<TransferFunction, SimError> {
+        let source = source.into();
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+        let (direction, _) = self.source_direction(&index, &source)?;
+        let selector = output.selector(&index, direction.nrows())?;
+        let lu = self.factor_a_mat()?;
+        let solve = |rhs| {
+            lu.solve(rhs)
+                .expect("the factorization was checked for singularity")
+        };
+
+        let response = solve(&direction);
+        let gain = selector.dot(&response);
+        // A voltage source's response is its branch current, which flows into the circuit
+        // with a negative sign; a current source's is the voltage across it
+        let is_voltage_source = matches!(
+            self.find_component(&source)
+                .and_then(|handle| self.component(handle)),
+            Some(Component::IVoltageSource(_))
+        );
+        let input_resistance = if is_voltage_source {
+            resistance(-1.0, direction.dot(&response))
+        } else {
+            direction.dot(&response)
+        };
+
+        let port = selector.dot(&solve(&selector));
+        let output_resistance = match output {
+            Output::Voltage(_) | Output::Differential(_, _) => port,
+            Output::Current(_) => resistance(-1.0, port),
+        };
+
+        Ok(TransferFunction {
+            gain,
+            input_resistance,
+            output_resistance,
+        })
+    }
+}
+
+/// `voltage / current`, infinite for an open circuit
+fn resistance(voltage: f64, current: f64) -> f64 {
+    if current == 0.0 {
+        f64::INFINITY
+    } else {
+        voltage / current
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn resistive_divider() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 5.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let r2 = resistor::Resistor::new(2, 0, 3e3);
+        let r3 = resistor::Resistor::new(2, 3, 500.0);
+        let r4 = resistor::Resistor::new(3, 0, 1e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::Resistor(r3));
+        net.add_component(Component::Resistor(r4));
+
+        // R2 || (R3 + R4) = 1k, so the output sees R1 || 1k
+        let tf = net
+            .transfer_function("V1", &Output::Voltage("2".to_string()))
+            .expect("transfer function should solve");
+        assert_float_relative_eq!(tf.gain, 0.5);
+        assert_float_relative_eq!(tf.input_resistance, 2e3);
+        assert_float_relative_eq!(tf.output_resistance, 500.0);
+
+        let tf = net
+            .transfer_function(
+                "V1",
+                &Output::Differential("2".to_string(), "3".to_string()),
+            )
+            .expect("transfer function should solve");
+        assert_float_relative_eq!(tf.gain, 0.5 * 500.0 / 1.5e3);
+        // R3 in parallel with R4 + (R1 || R2)
+        assert_float_relative_eq!(tf.output_resistance, 500.0 * 1750.0 / 2250.0);
+
+        // Looking into V1's branch: R1 + R2 || (R3 + R4)
+        let tf = net
+            .transfer_function("V1", &Output::Current("V1".into()))
+            .expect("transfer function should solve");
+        assert_float_relative_eq!(tf.gain, -1.0 / 2e3);
+        assert_float_relative_eq!(tf.output_resistance, 2e3);
+    }
+
+    #[test]
+    fn current_source_and_amplifier() {
+        // I1 drives R1; E1 amplifies V(1) by 10 into R2 through Rs
+        let mut net = Netlist::new();
+        let i1 = independent_current_source::ICurrentSource::new(0, 1, 1e-3);
+        let r1 = resistor::Resistor::new(1, 0, 2e3);
+        let e1 = vc_voltage_source::VCVoltageSource::new(1, 0, 2, 0, 10.0);
+        let rs = resistor::Resistor::new(2, 3, 100.0);
+        let r2 = resistor::Resistor::new(3, 0, 100.0);
+        net.add_named_component("I1", Component::ICurrentSource(i1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::VCVoltageSource(e1));
+        net.add_component(Component::Resistor(rs));
+        net.add_component(Component::Resistor(r2));
+
+        let tf = net
+            .transfer_function("I1", &Output::Voltage("3".to_string()))
+            .expect("transfer function should solve");
+        assert_float_relative_eq!(tf.gain, 2e3 * 10.0 / 2.0);
+        assert_float_relative_eq!(tf.input_resistance, 2e3);
+        assert_float_relative_eq!(tf.output_resistance, 50.0);
+
+        // Nothing loads the source at DC but a capacitor: infinite input resistance
+        let mut open = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let c1 = capacitor::Capacitor::new(1, 2, 1e-6);
+        let r1 = resistor::Resistor::new(2, 0, 1e3);
+        let v1 = open.add_component(Component::IVoltageSource(v1));
+        open.add_component(Component::Capacitor(c1));
+        open.add_component(Component::Resistor(r1));
+        let tf = open
+            .transfer_function(v1, &Output::Voltage("2".to_string()))
+            .expect("transfer function should solve");
+        assert_eq!(tf.gain, 0.0);
+        assert_eq!(tf.input_resistance, f64::INFINITY);
+        assert_float_relative_eq!(tf.output_resistance, 1e3);
+    }
+}
@@ -0,0 +1,1145 @@
+use crate::components::{Component, ComponentHandle, ComponentRef, Stamp};
+use crate::error::SimError;
+use crate::integration::{gear_coefficients, Companion, IntegrationMethod};
+use crate::netlist::Netlist;
+use crate::singularity;
+use crate::solution::{Output, SolutionIndex};
+use nalgebra::base::{DMatrix, DVector};
+use nalgebra::linalg::FullPivLU;
+use nalgebra::Dyn;
+
+/// Fraction of `tstep` used for the step that finds a consistent solution at t=0 when starting
+/// from initial conditions. It is short enough that the storage elements hold their initial
+/// state to working precision.
+const INITIAL_STEP_FRACTION: f64 = 1e-9;
+
+/// Adaptive steps shorter than this fraction of `tstop` are treated as a failure to converge
+const MIN_STEP_FRACTION: f64 = 1e-12;
+
+/// Limits on how much the adaptive control changes the step at once
+const MAX_STEP_GROWTH: f64 = 2.0;
+const MAX_STEP_SHRINK: f64 = 0.125;
+const STEP_SAFETY: f64 = 0.9;
+
+type Factored = Option<(Vec<f64>, FullPivLU<f64, Dyn, Dyn>)>;
+
+/// Past timepoints and the storage states at each, oldest first
+type History = Vec<(f64, Vec<f64>)>;
+
+/// Settings for `Netlist::transient_with_options`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransientOptions {
+    pub method: IntegrationMethod,
+    /// Start from the capacitor and inductor initial conditions instead of from the DC
+    /// operating point. A capacitor without its own takes the difference of its nodes' `.ic`
+    /// voltages, and anything left unset is zero. This is SPICE's UIC.
+    pub use_initial_conditions: bool,
+    /// Choose each step from a local truncation error estimate instead of always taking
+    /// `tstep`. `tstep` then only sets the size of the first step.
+    pub adaptive: bool,
+    /// Largest step the adaptive control may take; defaults to `tstop / 50`
+    pub max_step: Option<f64>,
+    /// Relative tolerance on capacitor voltages and inductor currents
+    pub reltol: f64,
+    /// Absolute tolerance on inductor currents, in amps
+    pub abstol: f64,
+    /// Absolute tolerance on capacitor voltages, in volts
+    pub vntol: f64,
+    /// Factor by which the truncation error estimate may exceed the tolerances, compensating
+    /// for its pessimism
+    pub trtol: f64,
+}
+
+impl Default for TransientOptions {
+    fn default() -> Self {
+        // The SPICE defaults
+        Self {
+            method: IntegrationMethod::default(),
+            use_initial_conditions: false,
+            adaptive: false,
+            max_step: None,
+            reltol: 1e-3,
+            abstol: 1e-12,
+            vntol: 1e-6,
+            trtol: 7.0,
+        }
+    }
+}
+
+/// Node voltages and branch currents at every timepoint of a transient analysis
+#[derive(Debug, Clone)]
+pub struct TransientResult {
+    index: SolutionIndex,
+    time: Vec<f64>,
+    solutions: Vec<DVector<f64>>,
+    rejected_steps: usize,
+}
+
+#[allow(dead_code)]
+impl TransientResult {
+    pub fn time(&self) -> &[f64] {
+        &self.time
+    }
+
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.time.is_empty()
+    }
+
+    /// How many steps the adaptive control discarded for exceeding the error tolerances
+    pub fn rejected_steps(&self) -> usize {
+        self.rejected_steps
+    }
+
+    /// The full MNA solution vector at timepoint `step`
+    pub fn solution(&self, step: usize) -> Option<&DVector<f64>> {
+        self.solutions.get(step)
+    }
+
+    /// The voltage of the node called `name` at every timepoint
+    pub fn node_voltage(&self, name: &str) -> Result<Vec<f64>, SimError> {
+        let row = self.index.node_row(name)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+
+    /// The branch current of a voltage source or inductor at every timepoint
+    pub fn branch_current(&self, component: impl Into<ComponentRef>) -> Result<Vec<f64>, SimError> {
+        let row = self.index.branch_row(component)?;
+        Ok(self.solutions.iter().map(|x| x[row]).collect())
+    }
+
+    /// The value of `output` at every timepoint
+    pub fn output(&self, output: &Output) -> Result<Vec<f64>, SimError> {
+        let selector = output.selector(&self.index, self.index.dim())?;
+        Ok(self.solutions.iter().map(|x| selector.dot(x)).collect())
+    }
+}
+
+/// The integrated quantity of one energy-storage element and its derivative-side counterpart:
+/// voltage and current for a capacitor, current and voltage for an inductor
+#[derive(Debug, Clone, Copy)]
+struct StorageState {
+    component: usize,
+    state: f64,
+    flow: f64,
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Simulates the circuit from its DC operating point to `tstop` in steps of `tstep` using
+    /// trapezoidal integration
+    pub fn transient(&mut self, tstop: f64, tstep: f64) -> Result<TransientResult, SimError> {
+        self.transient_with_options(tstop, tstep, &TransientOptions::default())
+    }
+
+    /// Simulates the circuit to `tstop` in steps of `tstep`. The final step is shortened if
+    /// `tstep` does not divide `tstop`, so the last timepoint is always `tstop`.
+    pub fn transient_with_options(
+        &mut self,
+        tstop: f64,
+        tstep: f64,
+        options: &TransientOptions,
+    ) -> Result<TransientResult, SimError> {
+        if !(tstep > 0.0 && tstop > 0.0) {
+            return Err(SimError::InvalidAnalysis(format!(
+                "transient needs positive tstop and tstep, got tstop={} and tstep={}",
+                tstop, tstep
+            )));
+        }
+        if let IntegrationMethod::Gear { order } = options.method {
+            if !(1..=IntegrationMethod::MAX_GEAR_ORDER).contains(&order) {
+                return Err(SimError::InvalidAnalysis(format!(
+                    "Gear order must be between 1 and {}, got {}",
+                    IntegrationMethod::MAX_GEAR_ORDER,
+                    order
+                )));
+            }
+        }
+        for (idx, component) in self.components().iter().enumerate() {
+            let waveform = match component {
+                Component::IVoltageSource(vs) => vs.waveform(),
+                Component::ICurrentSource(is) => is.waveform(),
+                Component::Resistor(_)
+                | Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_)
+                | Component::Capacitor(_)
+                | Component::Inductor(_) => continue,
+            };
+            waveform.validate().map_err(|message| {
+                SimError::InvalidAnalysis(format!(
+                    "{}: {}",
+                    self.component_label(ComponentHandle(idx)),
+                    message
+                ))
+            })?;
+        }
+        self.initialize_dc_mna()?;
+        if !self.is_linear() {
+            return Err(SimError::NonlinearCircuit);
+        }
+        let index = self.solution_index()?;
+
+        let (x0, states) =
+            if options.use_initial_conditions || !self.initial_conditions().is_empty() {
+                let initial = if options.use_initial_conditions {
+                    self.storage_states(&index, None)
+                } else {
+                    // Pinned nodes are released at t=0, so the storage elements on them start with
+                    // whatever flow the rest of the circuit drives
+                    let x = self.solve_pinned_dc(&index)?;
+                    self.storage_states(&index, Some(&x))
+                };
+                let h = tstep * INITIAL_STEP_FRACTION;
+                let (x, mut states) = self.companion_step(
+                    &index,
+                    &initial,
+                    &[],
+                    IntegrationMethod::BackwardEuler,
+                    h,
+                    &mut None,
+                )?;
+                // Keep the exact initial state and only adopt the consistent flows
+                for (state, initial) in states.iter_mut().zip(initial) {
+                    state.state = initial.state;
+                }
+                (x, states)
+            } else {
+                self.solve_dc_mna()?;
+                let x = self.x_mat.column(0).into_owned();
+                let states = self.storage_states(&index, Some(&x));
+                (x, states)
+            };
+
+        let mut time = vec![0.0];
+        let mut solutions = vec![x0];
+        let rejected_steps = if options.adaptive {
+            self.adaptive_steps(
+                &index,
+                states,
+                tstop,
+                tstep,
+                options,
+                &mut time,
+                &mut solutions,
+            )?
+        } else {
+            self.fixed_steps(
+                &index,
+                states,
+                tstop,
+                tstep,
+                options,
+                &mut time,
+                &mut solutions,
+            )?;
+            0
+        };
+
+        Ok(TransientResult {
+            index,
+            time,
+            solutions,
+            rejected_steps,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fixed_steps(
+        &self,
+        index: &SolutionIndex,
+        mut states: Vec<StorageState>,
+        tstop: f64,
+        tstep: f64,
+        options: &TransientOptions,
+        time: &mut Vec<f64>,
+        solutions: &mut Vec<DVector<f64>>,
+    ) -> Result<(), SimError> {
+        let num_steps = (tstop / tstep - 1e-9).ceil() as usize;
+        let mut history: History = vec![(0.0, state_values(&states))];
+        let mut factored = None;
+        for step in 1..=num_steps {
+            let t = if step == num_steps {
+                tstop
+            } else {
+                step as f64 * tstep
+            };
+            let h = t - time[step - 1];
+            let (x, next) =
+                self.companion_step(index, &states, &history, options.method, h, &mut factored)?;
+            states = next;
+            time.push(t);
+            solutions.push(x);
+            push_history(&mut history, t, &states, options.method.order());
+        }
+        Ok(())
+    }
+
+    /// Steps through time with the step chosen from the local truncation error of the storage
+    /// states. A step whose error exceeds the tolerances is rejected and retried shorter; an
+    /// accepted step sets the length of the next one. Steps end exactly on every breakpoint,
+    /// after which the integration restarts with a short step since the history before a
+    /// corner says nothing about the behaviour after it.
+    #[allow(clippy::too_many_arguments)]
+    fn adaptive_steps(
+        &self,
+        index: &SolutionIndex,
+        mut states: Vec<StorageState>,
+        tstop: f64,
+        tstep: f64,
+        options: &TransientOptions,
+        time: &mut Vec<f64>,
+        solutions: &mut Vec<DVector<f64>>,
+    ) -> Result<usize, SimError> {
+        let max_step = options.max_step.unwrap_or(tstop / 50.0).min(tstop);
+        let start_step = tstep.min(max_step) / 10.0;
+        let min_step = tstop * MIN_STEP_FRACTION;
+        let order = options.method.order();
+        let breakpoints = self.breakpoints(tstop);
+
+        let mut history: History = vec![(0.0, state_values(&states))];
+        let mut next_breakpoint = 0;
+        let mut factored = None;
+        let mut rejected_steps = 0;
+        let mut t = 0.0;
+        let mut h = start_step;
+        while t < tstop {
+            while breakpoints[next_breakpoint] <= t + min_step {
+                next_breakpoint += 1;
+            }
+            let breakpoint = breakpoints[next_breakpoint];
+            let mut h_try = h.min(max_step);
+            let hits_breakpoint = t + h_try >= breakpoint - min_step;
+            if hits_breakpoint {
+                h_try = breakpoint - t;
+            }
+
+            let (x, trial) = self.companion_step(
+                index,
+                &states,
+                &history,
+                options.method,
+                h_try,
+                &mut factored,
+            )?;
+            let t_new = if hits_breakpoint {
+                breakpoint
+            } else {
+                t + h_try
+            };
+
+            history.push((t_new, state_values(&trial)));
+            let ratio = self.step_ratio(&states, &history, options);
+            history.pop();
+            if ratio < 1.0 {
+                h = h_try * (STEP_SAFETY * ratio).max(MAX_STEP_SHRINK);
+                if h < min_step {
+                    return Err(SimError::TimestepTooSmall { time: t });
+                }
+                rejected_steps += 1;
+                continue;
+            }
+
+            t = t_new;
+            states = trial;
+            time.push(t);
+            solutions.push(x);
+            if hits_breakpoint {
+                history.clear();
+                h = start_step;
+            } else {
+                h = h_try * (STEP_SAFETY * ratio).clamp(MAX_STEP_SHRINK, MAX_STEP_GROWTH);
+            }
+            push_history(&mut history, t, &states, order);
+        }
+        Ok(rejected_steps)
+    }
+
+    /// Times the adaptive control must land on, in ascending order and ending with `tstop`.
+    /// These are the corners of the source waveforms, where the solution's derivatives jump.
+    fn breakpoints(&self, tstop: f64) -> Vec<f64> {
+        let mut times = vec![tstop];
+        for component in self.components() {
+            match component {
+                Component::IVoltageSource(vs) => times.extend(vs.waveform().breakpoints(tstop)),
+                Component::ICurrentSource(is) => times.extend(is.waveform().breakpoints(tstop)),
+                Component::Resistor(_)
+                | Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_)
+                | Component::Capacitor(_)
+                | Component::Inductor(_) => {}
+            }
+        }
+        times.sort_by(f64::total_cmp);
+        // Corners closer together than the shortest step are one corner
+        let min_step = tstop * MIN_STEP_FRACTION;
+        times.dedup_by(|later, earlier| *later - *earlier <= min_step);
+        if let Some(last) = times.last_mut() {
+            *last = tstop;
+        }
+        times
+    }
+
+    /// How much the step that produced the last point of `history` could be scaled while
+    /// keeping every storage state's truncation error within tolerance. Values below one mean
+    /// the step must be rejected. `states` are the states at the start of that step. Until a
+    /// restart has built up enough history for the method's own order, the error is estimated
+    /// at the highest order the points support, down to backward Euler from the two ends of the
+    /// step and the derivative at its start.
+    fn step_ratio(
+        &self,
+        states: &[StorageState],
+        history: &[(f64, Vec<f64>)],
+        options: &TransientOptions,
+    ) -> f64 {
+        let order = options.method.order().min(history.len().max(3) - 2);
+        let constant = if order == options.method.order() {
+            options.method.error_constant()
+        } else {
+            IntegrationMethod::Gear { order }.error_constant()
+        };
+        let points = &history[history.len() - (order + 2).min(history.len())..];
+        let times: Vec<f64> = points.iter().map(|(t, _)| *t).collect();
+        let h = times[times.len() - 1] - times[times.len() - 2];
+        // LTE = C * h^(k+1) * x^(k+1), with the derivative estimated as (k+1)! times the
+        // divided difference of order k+1
+        let factorial: f64 = (1..=order + 1).map(|k| k as f64).product();
+        let scale = constant * factorial * h.powi(order as i32 + 1);
+
+        let mut ratio = f64::INFINITY;
+        for (idx, s) in states.iter().enumerate() {
+            let values: Vec<f64> = points.iter().map(|(_, v)| v[idx]).collect();
+            let (k, absolute) = match &self.components()[s.component] {
+                Component::Capacitor(cap) => (cap.capacitance, options.vntol),
+                Component::Inductor(ind) => (ind.inductance, options.abstol),
+                _ => unreachable!("only storage elements carry state"),
+            };
+            let difference = if points.len() == 2 {
+                // Two points: the start counted twice, its derivative being flow / k
+                ((values[1] - values[0]) / h - s.flow / k) / h
+            } else {
+                divided_difference(&times, &values)
+            };
+            let lte = (scale * difference).abs();
+            let last = values.len() - 1;
+            let largest = values[last].abs().max(values[last - 1].abs());
+            let tolerance = options.trtol * (options.reltol * largest + absolute);
+            if lte > 0.0 {
+                ratio = ratio.min((tolerance / lte).powf(1.0 / (order as f64 + 1.0)));
+            }
+        }
+        ratio
+    }
+
+    /// Initial state of every energy-storage element, taken from the operating point `x` if
+    /// given and from the elements' initial conditions otherwise
+    fn storage_states(&self, index: &SolutionIndex, x: Option<&DVector<f64>>) -> Vec<StorageState> {
+        let mut states = vec![];
+        for (component_idx, component) in self.components().iter().enumerate() {
+            let state = match component {
+                Component::Capacitor(cap) => match x {
+                    Some(x) => index.voltage(x, cap.a_node) - index.voltage(x, cap.b_node),
+                    None => cap.initial_voltage.unwrap_or_else(|| {
+                        let pinned = |node| self.initial_conditions().get(&node).copied();
+                        pinned(cap.a_node).unwrap_or(0.0) - pinned(cap.b_node).unwrap_or(0.0)
+                    }),
+                },
+                Component::Inductor(ind) => match x {
+                    Some(x) => x[index.aux_row(ind.source_num)],
+                    None => ind.initial_current.unwrap_or(0.0),
+                },
+                Component::Resistor(_)
+                | Component::IVoltageSource(_)
+                | Component::ICurrentSource(_)
+                | Component::VCCurrentSource(_)
+                | Component::CCCurrentSource(_)
+                | Component::CCVoltageSource(_)
+                | Component::VCVoltageSource(_) => continue,
+            };
+            // At an operating point no current flows into a capacitor and no voltage develops
+            // across an inductor
+            states.push(StorageState {
+                component: component_idx,
+                state,
+                flow: 0.0,
+            });
+        }
+        states
+    }
+
+    /// Companion models for a step of length `h` from the last point of `history`. Gear uses
+    /// as many past points as its order allows, so it starts at order 1 and builds up.
+    fn companions(
+        &self,
+        states: &[StorageState],
+        history: &[(f64, Vec<f64>)],
+        method: IntegrationMethod,
+        h: f64,
+    ) -> Vec<Companion> {
+        if let IntegrationMethod::Gear { order } = method {
+            if history.len() > 1 {
+                let past: Vec<&(f64, Vec<f64>)> = history.iter().rev().take(order).collect();
+                let times: Vec<f64> = std::iter::once(past[0].0 + h)
+                    .chain(past.iter().map(|(t, _)| *t))
+                    .collect();
+                let coefficients = gear_coefficients(&times);
+                return states
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, s)| {
+                        let x_past: Vec<f64> = past.iter().map(|(_, v)| v[idx]).collect();
+                        let k = match &self.components()[s.component] {
+                            Component::Capacitor(cap) => cap.capacitance,
+                            Component::Inductor(ind) => ind.inductance,
+                            _ => unreachable!("only storage elements carry state"),
+                        };
+                        Companion::from_history(k, &coefficients, &x_past)
+                    })
+                    .collect();
+            }
+        }
+        states
+            .iter()
+            .map(|s| match &self.components()[s.component] {
+                Component::Capacitor(cap) => cap.companion(method, h, s.state, s.flow),
+                Component::Inductor(ind) => ind.companion(method, h, s.state, s.flow),
+                _ => unreachable!("only storage elements carry state"),
+            })
+            .collect()
+    }
+
+    /// The DC MNA matrix with every storage element replaced by its companion model
+    fn companion_matrix(
+        &self,
+        index: &SolutionIndex,
+        states: &[StorageState],
+        companions: &[Companion],
+    ) -> Result<DMatrix<f64>, SimError> {
+        let mut a_mat = (*self.a_mat).clone();
+        for (s, companion) in states.iter().zip(companions) {
+            match &self.components()[s.component] {
+                Component::Capacitor(cap) => {
+                    for Stamp(r, c, val) in cap.get_companion_gmat_stamps(companion) {
+                        a_mat[(index.row_of_id(r as u64)?, index.row_of_id(c as u64)?)] += val;
+                    }
+                }
+                Component::Inductor(ind) => {
+                    for Stamp(r, c, val) in ind.get_companion_dmat_stamps(companion) {
+                        a_mat[(index.aux_row(r as u64), index.aux_row(c as u64))] += val;
+                    }
+                }
+                _ => unreachable!("only storage elements carry state"),
+            }
+        }
+        Ok(a_mat)
+    }
+
+    /// The source vector at time `t` plus the history sources of every companion model
+    fn companion_rhs(
+        &self,
+        index: &SolutionIndex,
+        t: f64,
+        states: &[StorageState],
+        companions: &[Companion],
+    ) -> Result<DVector<f64>, SimError> {
+        let mut z_mat = self.source_vector(t)?;
+        for (s, companion) in states.iter().zip(companions) {
+            match &self.components()[s.component] {
+                Component::Capacitor(cap) => {
+                    for Stamp(r, _, val) in cap.get_companion_zmat_stamps(companion) {
+                        z_mat[index.row_of_id(r as u64)?] += val;
+                    }
+                }
+                Component::Inductor(ind) => {
+                    for Stamp(r, _, val) in ind.get_companion_zmat_stamps(companion) {
+                        z_mat[index.aux_row(r as u64)] += val;
+                    }
+                }
+                _ => unreachable!("only storage elements carry state"),
+            }
+        }
+        Ok(z_mat)
+    }
+
+    /// Reads the new state of every storage element out of the solution `x`
+    fn advance_states(
+        &self,
+        index: &SolutionIndex,
+        states: &mut [StorageState],
+        companions: &[Companion],
+        x: &DVector<f64>,
+    ) {
+        for (s, companion) in states.iter_mut().zip(companions) {
+            match &self.components()[s.component] {
+                Component::Capacitor(cap) => {
+                    let v = index.voltage(x, cap.a_node) - index.voltage(x, cap.b_node);
+                    s.state = v;
+                    s.flow = companion.conductance * v - companion.current;
+                }
+                Component::Inductor(ind) => {
+                    s.state = x[index.aux_row(ind.source_num)];
+                    s.flow = index.voltage(x, ind.a_node) - index.voltage(x, ind.b_node);
+                }
+                _ => unreachable!("only storage elements carry state"),
+            }
+        }
+    }
+
+    /// Solves a single companion step of length `h` from `states`, returning the solution and
+    /// the advanced states. The companion conductances usually repeat from step to step, so
+    /// `factored` keeps the last factorization for reuse while they do.
+    fn companion_step(
+        &self,
+        index: &SolutionIndex,
+        states: &[StorageState],
+        history: &[(f64, Vec<f64>)],
+        method: IntegrationMethod,
+        h: f64,
+        factored: &mut Factored,
+    ) -> Result<(DVector<f64>, Vec<StorageState>), SimError> {
+        let companions = self.companions(states, history, method, h);
+        let conductances: Vec<f64> = companions.iter().map(|c| c.conductance).collect();
+        if !matches!(factored, Some((factored_g, _)) if *factored_g == conductances) {
+            let a_mat = self.companion_matrix(index, states, &companions)?;
+            let lu = a_mat.clone().full_piv_lu();
+            if singularity::is_singular(&lu, &a_mat) {
+                return Err(SimError::SingularMatrix(self.singularity_report(&a_mat)));
+            }
+            *factored = Some((conductances, lu));
+        }
+        let (_, lu) = factored.as_ref().expect("factored above");
+        let t = history.last().map_or(0.0, |(t, _)| *t) + h;
+        let z_mat = self.companion_rhs(index, t, states, &companions)?;
+        let x = lu
+            .solve(&z_mat)
+            .expect("the factorization was checked for singularity");
+
+        let mut next = states.to_vec();
+        self.advance_states(index, &mut next, &companions, &x);
+        Ok((x, next))
+    }
+}
+
+fn state_values(states: &[StorageState]) -> Vec<f64> {
+    states.iter().map(|s| s.state).collect()
+}
+
+/// Records an accepted timepoint, keeping enough points for an order-`order` method and its
+/// error estimate
+fn push_history(history: &mut History, t: f64, states: &[StorageState], order: usize) {
+    history.push((t, state_values(states)));
+    if history.len() > order + 1 {
+        history.remove(0);
+    }
+}
+
+/// Newton divided difference of the highest order the points allow
+fn divided_difference(times: &[f64], values: &[f64]) -> f64 {
+    let mut table = values.to_vec();
+    for order in 1..times.len() {
+        for idx in 0..times.len() - order {
+            table[idx] = (table[idx + 1] - table[idx]) / (times[idx + order] - times[idx]);
+        }
+    }
+    table[0]
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::waveform::Waveform;
+    use assert_float_eq::*;
+
+    /// 1V step into R=1k, C=1u from an uncharged capacitor, tau = 1ms
+    #[allow(dead_code)]
+    fn rc_step() -> Netlist {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-6).with_initial_voltage(0.0);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Capacitor(c1));
+        net
+    }
+
+    #[test]
+    fn rc_step_trapezoidal() {
+        let mut net = rc_step();
+        let options = TransientOptions {
+            use_initial_conditions: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(5e-3, 1e-5, &options)
+            .expect("transient should run");
+        assert_eq!(result.len(), 501);
+
+        let v2 = result.node_voltage("2").unwrap();
+        assert!(v2[0].abs() < 1e-6);
+        for (t, v) in result.time().iter().zip(&v2) {
+            assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 1e-4);
+        }
+        // The source supplies the capacitor's charging current, which flows out of its + terminal
+        let i1 = result.branch_current("V1").unwrap();
+        assert_float_absolute_eq!(i1[100], -(-1.0f64).exp() * 1e-3, 1e-6);
+    }
+
+    #[test]
+    fn rc_step_backward_euler() {
+        let mut net = rc_step();
+        let options = TransientOptions {
+            method: IntegrationMethod::BackwardEuler,
+            use_initial_conditions: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(5e-3, 1e-5, &options)
+            .expect("transient should run");
+        let v2 = result.node_voltage("2").unwrap();
+        for (t, v) in result.time().iter().zip(&v2) {
+            assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 5e-3);
+        }
+    }
+
+    #[test]
+    fn rl_step() {
+        // 1V into R=1, L=1m from zero current, tau = 1ms
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 2, 1.0);
+        let l1 = inductor::Inductor::new(2, 0, 1e-3).with_initial_current(0.0);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        let l1 = net.add_component(Component::Inductor(l1));
+
+        let options = TransientOptions {
+            use_initial_conditions: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(3e-3, 1e-5, &options)
+            .expect("transient should run");
+        let i_l = result.branch_current(l1).unwrap();
+        let v2 = result.node_voltage("2").unwrap();
+        for ((t, i), v) in result.time().iter().zip(&i_l).zip(&v2) {
+            assert_float_absolute_eq!(*i, 1.0 - (-t / 1e-3).exp(), 1e-4);
+            assert_float_absolute_eq!(*v, (-t / 1e-3).exp(), 1e-4);
+        }
+    }
+
+    #[test]
+    fn starts_from_operating_point() {
+        // Without UIC the capacitor starts charged and nothing moves
+        let mut net = rc_step();
+        let result = net.transient(1e-3, 3e-4).expect("transient should run");
+        assert_eq!(result.time(), &[0.0, 3e-4, 6e-4, 9e-4, 1e-3]);
+        for v in result.node_voltage("2").unwrap() {
+            assert_float_absolute_eq!(v, 1.0, 1e-12);
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let mut net = rc_step();
+        assert!(matches!(
+            net.transient(1e-3, 0.0),
+            Err(SimError::InvalidAnalysis(_))
+        ));
+        let result = net.transient(1e-3, 1e-4).expect("transient should run");
+        assert_eq!(
+            result.node_voltage("9"),
+            Err(SimError::InvalidNode("9".to_string()))
+        );
+    }
+
+    #[test]
+    fn divided_differences() {
+        // The third divided difference of a cubic is its leading coefficient
+        let times = [0.0, 0.5, 2.0, 3.0];
+        let values: Vec<f64> = times.iter().map(|t| 2.0 * t * t * t - t + 1.0).collect();
+        assert_float_relative_eq!(divided_difference(&times, &values), 2.0);
+        assert_float_relative_eq!(divided_difference(&times[..3], &values[..3]), 2.0 * 2.5);
+    }
+
+    #[test]
+    fn adaptive_rc_step() {
+        let mut net = rc_step();
+        let options = TransientOptions {
+            use_initial_conditions: true,
+            adaptive: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(10e-3, 1e-5, &options)
+            .expect("transient should run");
+        assert_eq!(*result.time().last().unwrap(), 10e-3);
+        // A fixed step of 1e-5 would need 1000 points
+        assert!(result.len() < 200, "took {} points", result.len());
+        // The step grows as the exponential flattens out
+        let steps: Vec<f64> = result.time().windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(steps[steps.len() - 2] > 10.0 * steps[0]);
+
+        let v2 = result.node_voltage("2").unwrap();
+        for (t, v) in result.time().iter().zip(&v2) {
+            assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 2e-3);
+        }
+    }
+
+    #[test]
+    fn adaptive_rejects_long_steps() {
+        // Steps as long as the time constant are rejected until the error is acceptable
+        let mut net = rc_step();
+        let options = TransientOptions {
+            method: IntegrationMethod::BackwardEuler,
+            use_initial_conditions: true,
+            adaptive: true,
+            max_step: Some(1e-3),
+            reltol: 1e-4,
+            trtol: 1.0,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(5e-3, 1e-2, &options)
+            .expect("transient should run");
+        assert!(result.rejected_steps() > 0);
+        let v2 = result.node_voltage("2").unwrap();
+        for (t, v) in result.time().iter().zip(&v2) {
+            assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 1e-2);
+        }
+    }
+
+    #[test]
+    fn gear_rc_and_rl_steps() {
+        for order in [2, 4] {
+            let options = TransientOptions {
+                method: IntegrationMethod::Gear { order },
+                use_initial_conditions: true,
+                ..Default::default()
+            };
+            let mut net = rc_step();
+            let result = net
+                .transient_with_options(5e-3, 1e-5, &options)
+                .expect("transient should run");
+            let v2 = result.node_voltage("2").unwrap();
+            for (t, v) in result.time().iter().zip(&v2) {
+                assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 1e-4);
+            }
+
+            // 1V into R=1, L=1m from zero current, tau = 1ms
+            let mut net = Netlist::new();
+            let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+            let r1 = resistor::Resistor::new(1, 2, 1.0);
+            let l1 = inductor::Inductor::new(2, 0, 1e-3);
+            net.add_component(Component::IVoltageSource(v1));
+            net.add_component(Component::Resistor(r1));
+            let l1 = net.add_component(Component::Inductor(l1));
+            let result = net
+                .transient_with_options(3e-3, 1e-5, &options)
+                .expect("transient should run");
+            let i_l = result.branch_current(l1).unwrap();
+            for (t, i) in result.time().iter().zip(&i_l) {
+                assert_float_absolute_eq!(*i, 1.0 - (-t / 1e-3).exp(), 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn gear_adaptive() {
+        let mut net = rc_step();
+        let options = TransientOptions {
+            method: IntegrationMethod::Gear { order: 3 },
+            use_initial_conditions: true,
+            adaptive: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(10e-3, 1e-5, &options)
+            .expect("transient should run");
+        assert!(result.len() < 200, "took {} points", result.len());
+        let v2 = result.node_voltage("2").unwrap();
+        for (t, v) in result.time().iter().zip(&v2) {
+            assert_float_absolute_eq!(*v, 1.0 - (-t / 1e-3).exp(), 2e-3);
+        }
+    }
+
+    #[test]
+    fn gear_does_not_ring() {
+        // With steps ten times the time constant the trapezoidal rule overshoots and alternates
+        // around the final value, losing only a third of its error per step, while Gear
+        // damps the error out within a few steps
+        let mut net = rc_step();
+        let mut options = TransientOptions {
+            use_initial_conditions: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(1e-1, 1e-2, &options)
+            .expect("transient should run");
+        let v2 = result.node_voltage("2").unwrap();
+        assert!(v2.iter().any(|v| *v > 1.1));
+        assert!((v2[v2.len() - 1] - 1.0).abs() > 1e-2);
+
+        options.method = IntegrationMethod::Gear { order: 2 };
+        let result = net
+            .transient_with_options(1e-1, 1e-2, &options)
+            .expect("transient should run");
+        let v2 = result.node_voltage("2").unwrap();
+        assert!(v2.iter().all(|v| *v < 1.05));
+        assert_float_absolute_eq!(v2[v2.len() - 1], 1.0, 1e-6);
+
+        options.method = IntegrationMethod::Gear { order: 7 };
+        assert!(matches!(
+            net.transient_with_options(1e-1, 1e-2, &options),
+            Err(SimError::InvalidAnalysis(_))
+        ));
+    }
+
+    #[test]
+    fn tiny_pulse_period() {
+        let mut net = Netlist::new();
+        let pulse = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 1.0,
+            rise: 1e-9,
+            fall: 1e-9,
+            width: 1e-9,
+            period: 1e-17,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, pulse);
+        let r1 = resistor::Resistor::new(1, 0, 1e3);
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+        assert_eq!(
+            net.transient(2.0, 1e-1).unwrap_err(),
+            SimError::InvalidAnalysis(
+                "V1: PULSE period must be positive and at least rise + width + fall".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn pulse_into_rc() {
+        // A 1V pulse from 1ms to 3ms with 10us edges into R=1k, C=1u
+        let mut net = Netlist::new();
+        let pulse = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 1e-3,
+            rise: 1e-5,
+            fall: 1e-5,
+            width: 2e-3,
+            period: f64::INFINITY,
+        };
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, pulse);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-6);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Capacitor(c1));
+
+        let options = TransientOptions {
+            adaptive: true,
+            ..Default::default()
+        };
+        let result = net
+            .transient_with_options(6e-3, 1e-5, &options)
+            .expect("transient should run");
+        // Every corner of the pulse is a timepoint
+        for corner in [1e-3, 1.01e-3, 3.01e-3, 3.02e-3] {
+            assert!(
+                result.time().iter().any(|t| (t - corner).abs() < 1e-15),
+                "missing corner {}",
+                corner
+            );
+        }
+
+        let v1 = result.node_voltage("1").unwrap();
+        let v2 = result.node_voltage("2").unwrap();
+        for ((t, v1), v2) in result.time().iter().zip(&v1).zip(&v2) {
+            assert_float_absolute_eq!(*v1, expected_pulse(*t), 1e-12);
+            // Away from the edges the capacitor follows the ideal step response
+            if (1.2e-3..3.01e-3).contains(t) {
+                assert_float_absolute_eq!(*v2, 1.0 - (-(t - 1.005e-3) / 1e-3).exp(), 5e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn rejection_after_breakpoint() {
+        // A 1ns edge at 1ms into a 1us RC: the restart step after the corner is far too long for
+        // the response and must be rejected rather than accepted untested
+        let mut net = Netlist::new();
+        let edge = Waveform::Pwl(vec![
+            (0.0, 0.0),
+            (1e-3, 0.0),
+            (1e-3 + 1e-9, 1.0),
+            (2e-3, 1.0),
+        ]);
+        let v1 = independent_voltage_source::IVoltageSource::from_waveform(1, 0, edge);
+        let r1 = resistor::Resistor::new(1, 2, 1e3);
+        let c1 = capacitor::Capacitor::new(2, 0, 1e-9);
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Capacitor(c1));
+
+        for method in [
+            IntegrationMethod::Trapezoidal,
+            IntegrationMethod::Gear { order: 6 },
+        ] {
+            let options = TransientOptions {
+                method,
+                adaptive: true,
+                ..Default::default()
+            };
+            let result = net
+                .transient_with_options(2e-3, 1e-4, &options)
+                .expect("transient should run");
+            assert!(result.rejected_steps() > 0, "{:?}", method);
+            // The first step after the corner was cut down from the 4us restart step
+            let corner = result
+                .time()
+                .iter()
+                .position(|&t| t == 1e-3 + 1e-9)
+                .expect("the corner is a timepoint");
+            let first = result.time()[corner + 1] - result.time()[corner];
+            assert!(first < 1e-6, "{:?} took a first step of {}", method, first);
+            let v2 = result.node_voltage("2").unwrap();
+            for (t, v) in result.time().iter().zip(&v2) {
+                if *t > 1e-3 {
+                    let expected = 1.0 - (-(t - 1e-3 - 0.5e-9) / 1e-6).exp();
+                    assert_float_absolute_eq!(*v, expected, 2e-2);
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    fn expected_pulse(t: f64) -> f64 {
+        if t < 1e-3 {
+            0.0
+        } else if t < 1.01e-3 {
+            (t - 1e-3) / 1e-5
+        } else if t < 3.01e-3 {
+            1.0
+        } else if t < 3.02e-3 {
+            1.0 - (t - 3.01e-3) / 1e-5
+        } else {
+            0.0
+        }
+    }
+
+    #[test]
+    fn operating_point_uses_initial_value() {
+        let mut net = Netlist::new();
+        let sin = Waveform::Sin {
+            offset: 0.5,
+            amplitude: 1.0,
+            frequency: 1e3,
+            delay: 0.0,
+            damping: 0.0,
+            phase: 90.0,
+        };
+        let i1 = independent_current_source::ICurrentSource::from_waveform(0, 1, sin);
+        let r1 = resistor::Resistor::new(1, 0, 2.0);
+        net.add_component(Component::ICurrentSource(i1));
+        net.add_component(Component::Resistor(r1));
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("1").unwrap(), 3.0);
+
+        let result = net.transient(1e-3, 2.5e-4).expect("transient should run");
+        let v1 = result.node_voltage("1").unwrap();
+        for (v, expected) in v1.iter().zip([3.0, 1.0, -1.0, 1.0, 3.0]) {
+            assert_float_absolute_eq!(*v, expected, 1e-9);
+        }
+    }
+
+    #[test]
+    fn node_initial_conditions() {
+        let build = || {
+            let mut net = Netlist::new();
+            let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+            let r1 = resistor::Resistor::new(1, 2, 1e3);
+            let c1 = capacitor::Capacitor::new(2, 0, 1e-6);
+            net.add_component(Component::IVoltageSource(v1));
+            net.add_component(Component::Resistor(r1));
+            net.add_component(Component::Capacitor(c1));
+            net.set_initial_condition(2, 0.25);
+            net
+        };
+
+        // Pinned in the operating point, then released, and the same under UIC
+        for use_initial_conditions in [false, true] {
+            let mut net = build();
+            let options = TransientOptions {
+                use_initial_conditions,
+                ..Default::default()
+            };
+            let result = net
+                .transient_with_options(3e-3, 1e-5, &options)
+                .expect("transient should run");
+            let v1 = result.node_voltage("1").unwrap();
+            let v2 = result.node_voltage("2").unwrap();
+            assert_float_absolute_eq!(v1[0], 1.0, 1e-9);
+            assert_float_absolute_eq!(v2[0], 0.25, 1e-6);
+            for (t, v) in result.time().iter().zip(&v2) {
+                assert_float_absolute_eq!(*v, 1.0 - 0.75 * (-t / 1e-3).exp(), 1e-4);
+            }
+        }
+
+        // The plain operating point ignores initial conditions
+        let mut net = build();
+        net.initialize_dc_mna().expect("netlist should initialize");
+        net.solve_dc_mna().expect("netlist should solve");
+        assert_float_relative_eq!(net.get_node_voltage("2").unwrap(), 1.0);
+
+        net.set_initial_condition(7, 0.0);
+        assert_eq!(
+            net.transient(1e-3, 1e-4).unwrap_err(),
+            SimError::InvalidNode("7".to_string())
+        );
+    }
+
+    #[test]
+    fn source_overrides_initial_condition() {
+        let deck = "rc\n\
+                    V1 in 0 1\n\
+                    R1 in out 1k\n\
+                    C1 out 0 1u\n\
+                    .ic V(in)=0.5 V(out)=0.25\n";
+        let mut net = crate::parser::parse_netlist(deck).expect("deck should parse");
+        // V1 holds its node, and the initial condition on the capacitor still applies
+        let result = net.transient(3e-3, 1e-5).expect("transient should run");
+        let input = result.node_voltage("in").unwrap();
+        let out = result.node_voltage("out").unwrap();
+        assert!(input.iter().all(|&v| (v - 1.0).abs() < 1e-9));
+        assert_float_absolute_eq!(out[0], 0.25, 1e-6);
+        for (t, v) in result.time().iter().zip(&out) {
+            assert_float_absolute_eq!(*v, 1.0 - 0.75 * (-t / 1e-3).exp(), 1e-4);
+        }
+    }
+
+    #[test]
+    fn nodesets() {
+        let mut net = rc_step();
+        net.set_nodeset(2, 0.5);
+        net.set_nodeset(1, 2.0);
+        assert_eq!(net.nodesets().get(&2), Some(&0.5));
+
+        // A hint does not change the solution of a linear circuit
+        let result = net.transient(1e-3, 5e-4).expect("transient should run");
+        assert_eq!(result.node_voltage("2").unwrap()[0], 1.0);
+    }
+}
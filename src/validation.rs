@@ -0,0 +1,355 @@
+use crate::components::{Component, ComponentHandle, ComponentRef};
+use crate::netlist::Netlist;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+/// A structural problem that makes the DC MNA system singular or ill-defined
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// Nodes that are not connected to ground by any resistor or voltage-defining branch, and
+    /// that touch no current source either (e.g. nodes only used as VCCS sense terminals)
+    NoDcPathToGround { nodes: Vec<String> },
+    /// A loop made only of voltage-defining branches, whose voltages cannot all be satisfied
+    VoltageSourceLoop { components: Vec<String> },
+    /// Nodes whose only connection to the rest of the circuit is through current sources, so
+    /// KCL cannot hold unless those currents happen to cancel
+    CurrentSourceCutset {
+        components: Vec<String>,
+        nodes: Vec<String>,
+    },
+    /// A current-controlled source whose controlling source is missing or has no branch current
+    InvalidControllingSource {
+        component: String,
+        control: ComponentRef,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::NoDcPathToGround { nodes } => {
+                write!(f, "no DC path to ground from node(s) {}", nodes.join(", "))
+            }
+            Diagnostic::VoltageSourceLoop { components } => {
+                write!(f, "loop of voltage sources: {}", components.join(", "))
+            }
+            Diagnostic::CurrentSourceCutset { components, nodes } => write!(
+                f,
+                "node(s) {} only connect to the circuit through current source(s) {}",
+                nodes.join(", "),
+                components.join(", ")
+            ),
+            Diagnostic::InvalidControllingSource { component, control } => write!(
+                f,
+                "{} is controlled by {:?}, which is not a source with a branch current",
+                component, control
+            ),
+        }
+    }
+}
+
+/// How a component's output terminals constrain the DC solution
+enum Branch {
+    /// Fixes the current between the nodes
+    Current(u64, u64),
+    /// Conducts according to the voltage across the nodes
+    Conductive(u64, u64),
+    /// Fixes the voltage across the nodes
+    Voltage(u64, u64),
+    /// Carries no DC current at all
+    Open,
+}
+
+fn dc_branch(component: &Component) -> Branch {
+    match component {
+        Component::Resistor(res) => Branch::Conductive(res.a_node, res.b_node),
+        Component::IVoltageSource(vs) => Branch::Voltage(vs.positive_node, vs.negative_node),
+        Component::ICurrentSource(is) => Branch::Current(is.source_node, is.sink_node),
+        Component::VCCurrentSource(vccs) => Branch::Current(vccs.source_node, vccs.sink_node),
+        Component::CCCurrentSource(cccs) => Branch::Current(cccs.source_node, cccs.sink_node),
+        Component::CCVoltageSource(ccvs) => Branch::Voltage(ccvs.positive_node, ccvs.negative_node),
+        Component::VCVoltageSource(vcvs) => Branch::Voltage(vcvs.positive_node, vcvs.negative_node),
+        Component::Capacitor(_) => Branch::Open,
+        // An inductor is a short at DC
+        Component::Inductor(ind) => Branch::Voltage(ind.a_node, ind.b_node),
+    }
+}
+
+#[derive(Default)]
+struct UnionFind {
+    parent: HashMap<u64, u64>,
+}
+
+impl UnionFind {
+    fn find(&mut self, node: u64) -> u64 {
+        let parent = *self.parent.entry(node).or_insert(node);
+        if parent == node {
+            return node;
+        }
+        let root = self.find(parent);
+        self.parent.insert(node, root);
+        root
+    }
+
+    /// Returns false if the nodes were already connected
+    fn union(&mut self, a: u64, b: u64) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parent.insert(root_a, root_b);
+        true
+    }
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Checks the circuit graph for structural problems that would make the DC MNA system
+    /// singular. An empty list means the topology is sound.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let components = self.components();
+        let node_name = |id: u64| self.node_table().name_of(id);
+
+        for (idx, component) in components.iter().enumerate() {
+            if let Some(control) = component.controlling_source() {
+                let has_branch_current = self
+                    .find_component(control)
+                    .and_then(|handle| self.component(handle))
+                    .is_some_and(|source| source.source_num().is_some());
+                if !has_branch_current {
+                    diagnostics.push(Diagnostic::InvalidControllingSource {
+                        component: self.component_label(ComponentHandle(idx)),
+                        control: control.clone(),
+                    });
+                }
+            }
+        }
+
+        // Loops of voltage-defining branches: each branch that closes a cycle in the forest of
+        // those added so far is reported along with the path it closes
+        let mut voltage_forest = UnionFind::default();
+        let mut forest_edges: BTreeMap<u64, Vec<(u64, usize)>> = BTreeMap::new();
+        for (idx, component) in components.iter().enumerate() {
+            if let Branch::Voltage(a, b) = dc_branch(component) {
+                if voltage_forest.union(a, b) {
+                    forest_edges.entry(a).or_default().push((b, idx));
+                    forest_edges.entry(b).or_default().push((a, idx));
+                } else {
+                    let mut loop_members = forest_path(&forest_edges, a, b);
+                    loop_members.push(idx);
+                    diagnostics.push(Diagnostic::VoltageSourceLoop {
+                        components: loop_members
+                            .into_iter()
+                            .map(|idx| self.component_label(ComponentHandle(idx)))
+                            .collect(),
+                    });
+                }
+            }
+        }
+
+        // Islands of nodes with no conductive or voltage-defining path to ground
+        let mut dc_paths = UnionFind::default();
+        for component in components {
+            match dc_branch(component) {
+                Branch::Conductive(a, b) | Branch::Voltage(a, b) => {
+                    dc_paths.union(a, b);
+                }
+                Branch::Current(_, _) | Branch::Open => {}
+            }
+        }
+        let ground = dc_paths.find(0);
+        let mut islands: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+        for id in self.node_ids() {
+            let root = dc_paths.find(id);
+            if root != ground {
+                islands.entry(root).or_default().insert(id);
+            }
+        }
+        for island in islands.values() {
+            let cutset: Vec<String> = components
+                .iter()
+                .enumerate()
+                .filter(|(_, component)| match dc_branch(component) {
+                    Branch::Current(a, b) => island.contains(&a) != island.contains(&b),
+                    _ => false,
+                })
+                .map(|(idx, _)| self.component_label(ComponentHandle(idx)))
+                .collect();
+            let nodes = island.iter().map(|&id| node_name(id)).collect();
+            if cutset.is_empty() {
+                diagnostics.push(Diagnostic::NoDcPathToGround { nodes });
+            } else {
+                diagnostics.push(Diagnostic::CurrentSourceCutset {
+                    components: cutset,
+                    nodes,
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Component indices along the unique path between two nodes of a forest
+fn forest_path(edges: &BTreeMap<u64, Vec<(u64, usize)>>, from: u64, to: u64) -> Vec<usize> {
+    let mut came_from: HashMap<u64, (u64, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            break;
+        }
+        for &(next, idx) in edges.get(&node).into_iter().flatten() {
+            if next != from && !came_from.contains_key(&next) {
+                came_from.insert(next, (node, idx));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut path = vec![];
+    let mut node = to;
+    while let Some(&(prev, idx)) = came_from.get(&node) {
+        path.push(idx);
+        node = prev;
+    }
+    path.reverse();
+    path
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::error::SimError;
+
+    #[test]
+    fn valid_circuit() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 2, 5.0);
+        let r2 = resistor::Resistor::new(0, 2, 10.0);
+        let i1 = independent_current_source::ICurrentSource::new(0, 2, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_component(Component::Resistor(r2));
+        net.add_component(Component::ICurrentSource(i1));
+
+        assert!(net.validate().is_empty());
+    }
+
+    #[test]
+    fn floating_nodes() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let r2 = resistor::Resistor::new(2, 3, 1.0);
+        // Node 4 is only sensed, never driven
+        let g1 = vc_current_source::VCCurrentSource::new(4, 0, 1, 0, 1.0);
+
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("R2", Component::Resistor(r2))
+            .expect("new name");
+        net.add_named_component("G1", Component::VCCurrentSource(g1))
+            .expect("new name");
+
+        assert_eq!(
+            net.validate(),
+            vec![
+                Diagnostic::NoDcPathToGround {
+                    nodes: vec!["2".to_string(), "3".to_string()]
+                },
+                Diagnostic::NoDcPathToGround {
+                    nodes: vec!["4".to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn voltage_source_loop() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let v2 = independent_voltage_source::IVoltageSource::new(2, 1, 1.0);
+        let r1 = resistor::Resistor::new(2, 0, 1.0);
+        let e1 = vc_voltage_source::VCVoltageSource::new(1, 0, 2, 0, 2.0);
+
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("V2", Component::IVoltageSource(v2))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("E1", Component::VCVoltageSource(e1))
+            .expect("new name");
+
+        assert_eq!(
+            net.validate(),
+            vec![Diagnostic::VoltageSourceLoop {
+                components: vec!["V2".to_string(), "V1".to_string(), "E1".to_string()]
+            }]
+        );
+        assert!(matches!(
+            net.initialize_dc_mna(),
+            Err(SimError::Topology(diagnostics)) if diagnostics.len() == 1
+        ));
+    }
+
+    #[test]
+    fn current_source_cutset() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let i1 = independent_current_source::ICurrentSource::new(1, 2, 1.0);
+        let r2 = resistor::Resistor::new(2, 3, 1.0);
+        let f1 = cc_current_source::CCCurrentSource::new("V1", 3, 0, 1.0);
+
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_named_component("R1", Component::Resistor(r1))
+            .expect("new name");
+        net.add_named_component("I1", Component::ICurrentSource(i1))
+            .expect("new name");
+        net.add_named_component("R2", Component::Resistor(r2))
+            .expect("new name");
+        net.add_component(Component::CCCurrentSource(f1));
+
+        assert_eq!(
+            net.validate(),
+            vec![Diagnostic::CurrentSourceCutset {
+                components: vec!["I1".to_string(), "#4".to_string()],
+                nodes: vec!["2".to_string(), "3".to_string()]
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_controlling_source() {
+        let mut net = Netlist::new();
+
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 1.0);
+        let f1 = cc_current_source::CCCurrentSource::new(ComponentHandle(7), 1, 0, 1.0);
+
+        net.add_component(Component::IVoltageSource(v1));
+        net.add_component(Component::Resistor(r1));
+        net.add_named_component("F1", Component::CCCurrentSource(f1))
+            .expect("new name");
+
+        assert_eq!(
+            net.validate(),
+            vec![Diagnostic::InvalidControllingSource {
+                component: "F1".to_string(),
+                control: ComponentHandle(7).into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,464 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Time dependence of an independent source, following the SPICE source functions. Times are in
+/// seconds, frequencies in hertz and phases in degrees.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    /// A constant value
+    Dc(f64),
+    /// A trapezoidal pulse train between `initial` and `pulsed`. A `period` of infinity gives a
+    /// single pulse, and a finite one must be at least `rise + width + fall`.
+    Pulse {
+        initial: f64,
+        pulsed: f64,
+        delay: f64,
+        rise: f64,
+        fall: f64,
+        width: f64,
+        period: f64,
+    },
+    /// A sinusoid that starts after `delay` and decays at rate `damping` (1/s)
+    Sin {
+        offset: f64,
+        amplitude: f64,
+        frequency: f64,
+        delay: f64,
+        damping: f64,
+        phase: f64,
+    },
+    /// Piecewise linear through `(time, value)` points in ascending time, holding the first
+    /// value before the first point and the last value after the last
+    Pwl(Vec<(f64, f64)>),
+    /// An exponential rise from `initial` towards `pulsed` starting at `rise_delay`, followed by
+    /// an exponential fall back towards `initial` starting at `fall_delay`. A time constant of
+    /// zero gives a step.
+    Exp {
+        initial: f64,
+        pulsed: f64,
+        rise_delay: f64,
+        rise_tau: f64,
+        fall_delay: f64,
+        fall_tau: f64,
+    },
+    /// A single-frequency FM sinusoid
+    Sffm {
+        offset: f64,
+        amplitude: f64,
+        carrier: f64,
+        modulation_index: f64,
+        signal: f64,
+    },
+}
+
+#[allow(dead_code)]
+impl Waveform {
+    /// Reads a piecewise linear waveform from a text file with one `time value` pair per line,
+    /// separated by whitespace or a comma. Blank lines and lines starting with `*` or `#` are
+    /// skipped.
+    pub fn pwl_from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut points = vec![];
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('*') || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected a time and a value", idx + 1),
+                )
+            };
+            let fields: Vec<&str> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|field| !field.is_empty())
+                .collect();
+            let [time, value] = fields[..] else {
+                return Err(invalid());
+            };
+            let time: f64 = time.parse().map_err(|_| invalid())?;
+            let value: f64 = value.parse().map_err(|_| invalid())?;
+            points.push((time, value));
+        }
+        if !pwl_times_ascend(&points) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "PWL times must be in ascending order",
+            ));
+        }
+        Ok(Waveform::Pwl(points))
+    }
+
+    /// The value of the waveform at time `t`
+    pub fn value(&self, t: f64) -> f64 {
+        match self {
+            Waveform::Dc(value) => *value,
+            Waveform::Pulse {
+                initial,
+                pulsed,
+                delay,
+                rise,
+                fall,
+                width,
+                period,
+            } => {
+                if t < *delay {
+                    return *initial;
+                }
+                let mut tt = t - delay;
+                if period.is_finite() && *period > 0.0 {
+                    tt %= period;
+                }
+                if tt < *rise {
+                    initial + (pulsed - initial) * tt / rise
+                } else if tt < rise + width {
+                    *pulsed
+                } else if tt < rise + width + fall {
+                    pulsed + (initial - pulsed) * (tt - rise - width) / fall
+                } else {
+                    *initial
+                }
+            }
+            Waveform::Sin {
+                offset,
+                amplitude,
+                frequency,
+                delay,
+                damping,
+                phase,
+            } => {
+                let phase = phase.to_radians();
+                if t < *delay {
+                    return offset + amplitude * phase.sin();
+                }
+                let tt = t - delay;
+                offset
+                    + amplitude * (-damping * tt).exp() * (2.0 * PI * frequency * tt + phase).sin()
+            }
+            Waveform::Pwl(points) => {
+                let Some(&(first_time, first_value)) = points.first() else {
+                    return 0.0;
+                };
+                if t <= first_time {
+                    return first_value;
+                }
+                for pair in points.windows(2) {
+                    let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+                    if t <= t1 {
+                        if t1 == t0 {
+                            return v1;
+                        }
+                        return v0 + (v1 - v0) * (t - t0) / (t1 - t0);
+                    }
+                }
+                points[points.len() - 1].1
+            }
+            Waveform::Exp {
+                initial,
+                pulsed,
+                rise_delay,
+                rise_tau,
+                fall_delay,
+                fall_tau,
+            } => {
+                // The fraction of the way to the target after `elapsed`, with a zero time
+                // constant jumping straight there
+                let settled = |elapsed: f64, tau: f64| {
+                    if tau > 0.0 {
+                        1.0 - (-elapsed / tau).exp()
+                    } else {
+                        1.0
+                    }
+                };
+                let mut value = *initial;
+                if t >= *rise_delay {
+                    value += (pulsed - initial) * settled(t - rise_delay, *rise_tau);
+                }
+                if t >= *fall_delay {
+                    value += (initial - pulsed) * settled(t - fall_delay, *fall_tau);
+                }
+                value
+            }
+            Waveform::Sffm {
+                offset,
+                amplitude,
+                carrier,
+                modulation_index,
+                signal,
+            } => {
+                offset
+                    + amplitude
+                        * (2.0 * PI * carrier * t
+                            + modulation_index * (2.0 * PI * signal * t).sin())
+                        .sin()
+            }
+        }
+    }
+
+    /// Checks for parameters that make the waveform meaningless: a pulse period must be
+    /// positive and no shorter than the pulse itself
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Waveform::Pulse {
+                rise,
+                fall,
+                width,
+                period,
+                ..
+            } if !(*period > 0.0 && *period >= rise + width + fall) => {
+                Err("PULSE period must be positive and at least rise + width + fall".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The same time function moved up or down by `delta`
+    pub fn shifted(&self, delta: f64) -> Self {
+        let mut waveform = self.clone();
+        match &mut waveform {
+            Waveform::Dc(value) => *value += delta,
+            Waveform::Pulse {
+                initial, pulsed, ..
+            }
+            | Waveform::Exp {
+                initial, pulsed, ..
+            } => {
+                *initial += delta;
+                *pulsed += delta;
+            }
+            Waveform::Sin { offset, .. } | Waveform::Sffm { offset, .. } => *offset += delta,
+            Waveform::Pwl(points) => points.iter_mut().for_each(|(_, value)| *value += delta),
+        }
+        waveform
+    }
+
+    /// The same time function with every value multiplied by `factor`
+    pub fn scaled(&self, factor: f64) -> Self {
+        let mut waveform = self.clone();
+        match &mut waveform {
+            Waveform::Dc(value) => *value *= factor,
+            Waveform::Pulse {
+                initial, pulsed, ..
+            }
+            | Waveform::Exp {
+                initial, pulsed, ..
+            } => {
+                *initial *= factor;
+                *pulsed *= factor;
+            }
+            Waveform::Sin {
+                offset, amplitude, ..
+            }
+            | Waveform::Sffm {
+                offset, amplitude, ..
+            } => {
+                *offset *= factor;
+                *amplitude *= factor;
+            }
+            Waveform::Pwl(points) => points.iter_mut().for_each(|(_, value)| *value *= factor),
+        }
+        waveform
+    }
+
+    /// Times in `(0, tstop]` where the waveform has a corner, in ascending order. A transient
+    /// analysis must land on these to avoid stepping over the discontinuities in slope.
+    pub fn breakpoints(&self, tstop: f64) -> Vec<f64> {
+        let mut times = match self {
+            Waveform::Dc(_) | Waveform::Sffm { .. } => vec![],
+            Waveform::Pulse {
+                delay,
+                rise,
+                fall,
+                width,
+                period,
+                ..
+            } => {
+                let corners = [0.0, *rise, rise + width, rise + width + fall];
+                // Multiplying rather than accumulating the period keeps a tiny period from
+                // stalling, and an invalid one gives a single pulse
+                let repeats = period.is_finite() && *period > 0.0 && *period >= corners[3];
+                let (periods, step) = if repeats && tstop > *delay {
+                    (((tstop - delay) / period).ceil() as usize, *period)
+                } else {
+                    (0, 0.0)
+                };
+                (0..=periods)
+                    .flat_map(|k| {
+                        let start = delay + k as f64 * step;
+                        corners.map(|corner| start + corner)
+                    })
+                    .collect()
+            }
+            Waveform::Sin { delay, .. } => vec![*delay],
+            Waveform::Pwl(points) => points.iter().map(|(time, _)| *time).collect(),
+            Waveform::Exp {
+                rise_delay,
+                fall_delay,
+                ..
+            } => vec![*rise_delay, *fall_delay],
+        };
+        times.retain(|&time| time > 0.0 && time <= tstop);
+        times.sort_by(f64::total_cmp);
+        times.dedup();
+        times
+    }
+}
+
+/// True if the times of a piecewise linear waveform never decrease
+pub fn pwl_times_ascend(points: &[(f64, f64)]) -> bool {
+    points.windows(2).all(|pair| pair[0].0 <= pair[1].0)
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use assert_float_eq::*;
+
+    #[test]
+    fn pulse() {
+        let pulse = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 5.0,
+            delay: 1.0,
+            rise: 1.0,
+            fall: 2.0,
+            width: 3.0,
+            period: 10.0,
+        };
+        assert_float_absolute_eq!(pulse.value(0.5), 0.0);
+        assert_float_absolute_eq!(pulse.value(1.5), 2.5);
+        assert_float_absolute_eq!(pulse.value(4.0), 5.0);
+        assert_float_absolute_eq!(pulse.value(6.0), 2.5);
+        assert_float_absolute_eq!(pulse.value(9.0), 0.0);
+        // Second period
+        assert_float_absolute_eq!(pulse.value(11.5), 2.5);
+        assert_eq!(
+            pulse.breakpoints(12.0),
+            vec![1.0, 2.0, 5.0, 7.0, 11.0, 12.0]
+        );
+        assert!(pulse.validate().is_ok());
+
+        // A period below the float spacing at the delay is invalid, and gives one pulse rather
+        // than a loop that never advances
+        let pulse_with_period = |period: f64| Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 1.0,
+            rise: 1e-9,
+            fall: 1e-9,
+            width: 1e-9,
+            period,
+        };
+        let tiny = pulse_with_period(1e-17);
+        assert!(tiny.validate().is_err());
+        assert_eq!(tiny.breakpoints(2.0).len(), 4);
+        assert!(pulse_with_period(2e-9).validate().is_err());
+        assert!(pulse_with_period(0.0).validate().is_err());
+        assert!(pulse_with_period(4e-9).validate().is_ok());
+    }
+
+    #[test]
+    fn sin() {
+        let sin = Waveform::Sin {
+            offset: 1.0,
+            amplitude: 2.0,
+            frequency: 1e3,
+            delay: 1e-3,
+            damping: 0.0,
+            phase: 90.0,
+        };
+        assert_float_absolute_eq!(sin.value(0.0), 3.0);
+        assert_float_absolute_eq!(sin.value(1.25e-3), 1.0, 1e-12);
+        assert_float_absolute_eq!(sin.value(1.5e-3), -1.0, 1e-12);
+        assert_eq!(sin.breakpoints(1.0), vec![1e-3]);
+
+        let damped = Waveform::Sin {
+            offset: 0.0,
+            amplitude: 1.0,
+            frequency: 1.0,
+            delay: 0.0,
+            damping: 2.0,
+            phase: 0.0,
+        };
+        assert_float_absolute_eq!(damped.value(1.25), (-2.5f64).exp(), 1e-12);
+    }
+
+    #[test]
+    fn pwl() {
+        let pwl = Waveform::Pwl(vec![(1.0, 0.0), (2.0, 4.0), (2.0, 1.0), (4.0, 2.0)]);
+        assert_float_absolute_eq!(pwl.value(0.0), 0.0);
+        assert_float_absolute_eq!(pwl.value(1.5), 2.0);
+        assert_float_absolute_eq!(pwl.value(3.0), 1.5);
+        assert_float_absolute_eq!(pwl.value(5.0), 2.0);
+        assert_eq!(pwl.breakpoints(3.0), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn pwl_file() {
+        let path = std::env::temp_dir().join(format!("ana_sim_pwl_{}.txt", std::process::id()));
+        fs::write(&path, "# time value\n0 0\n1e-3, 2.5\n\n2e-3 1\n").unwrap();
+        let pwl = Waveform::pwl_from_file(&path).expect("file should parse");
+        assert_eq!(
+            pwl,
+            Waveform::Pwl(vec![(0.0, 0.0), (1e-3, 2.5), (2e-3, 1.0)])
+        );
+
+        fs::write(&path, "0 0\n2 1\n1 2\n").unwrap();
+        let err = Waveform::pwl_from_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::write(&path, "0 0 0\n").unwrap();
+        let err = Waveform::pwl_from_file(&path).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: expected a time and a value");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn exp() {
+        let exp = Waveform::Exp {
+            initial: 0.0,
+            pulsed: 1.0,
+            rise_delay: 1.0,
+            rise_tau: 1.0,
+            fall_delay: 3.0,
+            fall_tau: 0.5,
+        };
+        assert_float_absolute_eq!(exp.value(0.5), 0.0);
+        assert_float_absolute_eq!(exp.value(2.0), 1.0 - (-1.0f64).exp());
+        // Rising for 2.5s and falling for 1s
+        assert_float_absolute_eq!(exp.value(3.5), (-1.0f64).exp() - (-2.5f64).exp(), 1e-12);
+        assert_eq!(exp.breakpoints(10.0), vec![1.0, 3.0]);
+
+        // Zero time constants are steps, even exactly at the delays
+        let step = Waveform::Exp {
+            initial: 0.0,
+            pulsed: 1.0,
+            rise_delay: 1.0,
+            rise_tau: 0.0,
+            fall_delay: 3.0,
+            fall_tau: 0.0,
+        };
+        assert_eq!(step.value(0.5), 0.0);
+        assert_eq!(step.value(1.0), 1.0);
+        assert_eq!(step.value(3.0), 0.0);
+    }
+
+    #[test]
+    fn sffm() {
+        let sffm = Waveform::Sffm {
+            offset: 1.0,
+            amplitude: 2.0,
+            carrier: 1e3,
+            modulation_index: 0.5,
+            signal: 100.0,
+        };
+        assert_float_absolute_eq!(sffm.value(0.0), 1.0);
+        let t = 0.3e-3;
+        let expected = 1.0 + 2.0 * (2.0 * PI * 1e3 * t + 0.5 * (2.0 * PI * 100.0 * t).sin()).sin();
+        assert_float_absolute_eq!(sffm.value(t), expected);
+        assert!(sffm.breakpoints(1.0).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::error::SimError;
+use crate::netlist::Netlist;
+use crate::parameter::Parameter;
+use crate::solution::Output;
+
+/// One extreme of a worst-case analysis and the component values that produce it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corner {
+    pub output: f64,
+    pub values: Vec<(Parameter, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorstCaseResult {
+    pub nominal: f64,
+    pub max: Corner,
+    pub min: Corner,
+}
+
+#[allow(dead_code)]
+impl Netlist {
+    /// Pushes every toleranced parameter to the end of its range that raises `output`, and then
+    /// to the end that lowers it. The direction comes from the sign of the output's adjoint
+    /// sensitivity to each parameter at the nominal operating point, which is exact for a
+    /// linear circuit when the sensitivities keep their sign across the tolerance range. Lot
+    /// and device tolerances add, and each component moves on its own, so lot correlation is
+    /// ignored and the corners are conservative.
+    pub fn worst_case(&mut self, output: &Output) -> Result<WorstCaseResult, SimError> {
+        let parameters = self.toleranced_parameters();
+        let sensitivities = self.sensitivity(output)?;
+        let nominal = sensitivities.output();
+
+        let mut max_values = Vec::with_capacity(parameters.len());
+        let mut min_values = Vec::with_capacity(parameters.len());
+        for (parameter, value, tolerance) in &parameters {
+            // Capacitors and inductors have no DC sensitivity and stay put
+            let slope = match parameter {
+                // Scaling a source's waveform scales its operating-point value along with it
+                Parameter::SourceScale(source) => sensitivities
+                    .get(&Parameter::SourceValue(source.clone()))
+                    .map_or(0.0, |sensitivity| sensitivity.absolute * sensitivity.value),
+                _ => sensitivities
+                    .get(parameter)
+                    .map_or(0.0, |sensitivity| sensitivity.absolute),
+            };
+            let high = value * (1.0 + tolerance.bound());
+            let low = value * (1.0 - tolerance.bound());
+            let (up, down) = if slope >= 0.0 {
+                (high, low)
+            } else {
+                (low, high)
+            };
+            max_values.push((parameter.clone(), up));
+            min_values.push((parameter.clone(), down));
+        }
+
+        let max = self.with_parameters(&max_values, |net| net.dc_output(output))?;
+        let min = self.with_parameters(&min_values, |net| net.dc_output(output))?;
+        Ok(WorstCaseResult {
+            nominal,
+            max: Corner {
+                output: max,
+                values: max_values,
+            },
+            min: Corner {
+                output: min,
+                values: min_values,
+            },
+        })
+    }
+
+    /// Solves the operating point and measures `output`
+    fn dc_output(&mut self, output: &Output) -> Result<f64, SimError> {
+        self.initialize_dc_mna()?;
+        self.solve_dc_mna()?;
+        let index = self.solution_index()?;
+        output.value(&index, &self.x_mat.column(0).into_owned())
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::tolerance::Tolerance;
+    use assert_float_eq::*;
+
+    #[test]
+    fn divider_corners() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 10.0)
+            .with_tolerance(Tolerance::uniform(0.1));
+        let r1 = resistor::Resistor::new(1, 2, 1e3).with_tolerance(Tolerance::uniform(0.05));
+        let r2 = resistor::Resistor::new(2, 0, 1e3)
+            .with_tolerance(Tolerance::gaussian(0.01).with_lot("reel", 0.04));
+        let r3 = resistor::Resistor::new(2, 0, 1e3);
+        let v1 = net.add_component(Component::IVoltageSource(v1));
+        let r1 = net.add_component(Component::Resistor(r1));
+        let r2 = net.add_component(Component::Resistor(r2));
+        net.add_component(Component::Resistor(r3));
+
+        let result = net
+            .worst_case(&Output::Voltage("2".to_string()))
+            .expect("worst case should run");
+        assert_float_relative_eq!(result.nominal, 10.0 / 3.0);
+
+        // V(2) rises with V1 and R2 and falls with R1
+        let divider =
+            |v: f64, r1: f64, r2: f64| v * (r2 * 1e3 / (r2 + 1e3)) / (r1 + r2 * 1e3 / (r2 + 1e3));
+        assert_eq!(
+            result.max.values,
+            vec![
+                (Parameter::SourceScale(v1.into()), 1.1),
+                (Parameter::Resistance(r1.into()), 950.0),
+                (Parameter::Resistance(r2.into()), 1050.0),
+            ]
+        );
+        assert_float_relative_eq!(result.max.output, divider(11.0, 950.0, 1050.0));
+        assert_float_relative_eq!(result.min.output, divider(9.0, 1050.0, 950.0));
+        assert_eq!(result.min.values[1].1, 1050.0);
+
+        // The netlist keeps its nominal values
+        assert_eq!(net.parameter(&Parameter::Resistance(r1.into())), Ok(1e3));
+    }
+
+    #[test]
+    fn branch_current_corners() {
+        let mut net = Netlist::new();
+        let v1 = independent_voltage_source::IVoltageSource::new(1, 0, 1.0);
+        let r1 = resistor::Resistor::new(1, 0, 1.0).with_tolerance(Tolerance::uniform(0.5));
+        net.add_named_component("V1", Component::IVoltageSource(v1))
+            .expect("new name");
+        net.add_component(Component::Resistor(r1));
+
+        // The source current is -1/R1, so its maximum comes from the largest resistance
+        let result = net
+            .worst_case(&Output::Current("V1".into()))
+            .expect("worst case should run");
+        assert_float_relative_eq!(result.max.output, -1.0 / 1.5);
+        assert_float_relative_eq!(result.min.output, -1.0 / 0.5);
+        assert_eq!(result.max.values[0].1, 1.5);
+    }
+}